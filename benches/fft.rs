@@ -0,0 +1,43 @@
+use bls_12_381::Fr as Scalar;
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion};
+use lookup::{Fft, FftStrategy};
+use rand::rngs::OsRng;
+use zkstd::common::Group;
+
+fn fft(c: &mut Criterion) {
+    let mut group = c.benchmark_group("fft");
+    for k in [10, 14, 18] {
+        let coeffs = (0..1 << k)
+            .map(|_| Scalar::random(OsRng))
+            .collect::<Vec<_>>();
+        let strategies = [
+            ("recursive", FftStrategy::Recursive),
+            (
+                "radix4",
+                FftStrategy::Radix4 {
+                    parallel_threshold: 1 << 12,
+                },
+            ),
+            (
+                "radix4_serial",
+                FftStrategy::Radix4 {
+                    parallel_threshold: usize::MAX,
+                },
+            ),
+        ];
+        for (name, strategy) in strategies {
+            let fft = Fft::with_strategy(k, strategy);
+            group.bench_with_input(BenchmarkId::new(name, k), &coeffs, |b, coeffs| {
+                b.iter_batched(
+                    || coeffs.clone(),
+                    |mut coeffs| fft.evaluate_in_place(&mut coeffs),
+                    BatchSize::LargeInput,
+                )
+            });
+        }
+    }
+    group.finish();
+}
+
+criterion_group!(benches, fft);
+criterion_main!(benches);
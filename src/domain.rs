@@ -0,0 +1,223 @@
+//! multiplicative subgroup H = {1, ω, ..., ω^n-1} of n = 2^k or 3 * 2^k used as evaluation domain
+use zkstd::common::FftField;
+
+use crate::{
+    evaluations::Evaluations,
+    fft::{Fft, FftStrategy},
+    inner_product::DensePolynomial,
+    utils::{batch_inversion, powers_of},
+};
+
+pub(crate) struct EvaluationDomain<F: FftField> {
+    fft: Fft<F>,
+    // n as field element
+    size: F,
+}
+
+impl<F: FftField> EvaluationDomain<F> {
+    pub(crate) fn new(k: usize) -> Self {
+        Self::with_size(1 << k, FftStrategy::default())
+    }
+
+    /// domain of n = 2^k or n = 3 * 2^k points transformed with strategy
+    pub(crate) fn with_size(n: usize, strategy: FftStrategy) -> Self {
+        let fft = Fft::with_size(n, strategy);
+        let size = F::from(n as u64);
+        Self { fft, size }
+    }
+
+    pub(crate) fn fft(&self) -> &Fft<F> {
+        &self.fft
+    }
+
+    pub(crate) fn size(&self) -> usize {
+        self.fft.size()
+    }
+
+    /// primitive n th root of unity ω
+    pub(crate) fn generator(&self) -> F {
+        self.fft.generator()
+    }
+
+    /// 1, ω, ω^2, ..., ω^n-1
+    pub(crate) fn elements(&self) -> impl Iterator<Item = F> {
+        let ω = self.generator();
+        (0..self.size()).scan(F::one(), move |acc, _| {
+            let element = *acc;
+            *acc *= ω;
+            Some(element)
+        })
+    }
+
+    /// domain of 2^log_ratio times size with same strategy
+    pub(crate) fn extended(&self, log_ratio: usize) -> Self {
+        Self::with_size(self.size() << log_ratio, self.fft.strategy())
+    }
+
+    /// 1 / Z_H(x) on coset g * H' of extended domain H'
+    /// Z_H(g * ω'^i) = g^n ω'^(n i) - 1 only takes |H'| / n values so i th inverse is at i % ratio
+    pub(crate) fn coset_vanishing_inverses(&self, extended: &Self) -> Vec<F> {
+        let ratio = extended.size() / self.size();
+        let g_n = F::MULTIPLICATIVE_GENERATOR.pow(self.size() as u64);
+        let ω_n = extended.generator().pow(self.size() as u64);
+        let mut inverses = powers_of(ω_n, ratio)
+            .into_iter()
+            .map(|power| g_n * power - F::one())
+            .collect::<Vec<_>>();
+        batch_inversion(&mut inverses);
+        inverses
+    }
+
+    /// Z_H(x) = x^n - 1
+    pub(crate) fn vanishing_poly(&self) -> DensePolynomial<F> {
+        let mut coeffs = vec![F::zero(); self.size() + 1];
+        coeffs[0] = -F::one();
+        coeffs[self.size()] = F::one();
+        DensePolynomial::new(coeffs)
+    }
+
+    /// Z_H(ζ) = ζ^n - 1
+    pub(crate) fn evaluate_vanishing(&self, ζ: F) -> F {
+        ζ.pow(self.size() as u64) - F::one()
+    }
+
+    /// L_i(x) which is one at ω^i and zero at the rest of domain
+    pub(crate) fn lagrange_basis(&self, i: usize) -> DensePolynomial<F> {
+        let mut evals = vec![F::zero(); self.size()];
+        evals[i] = F::one();
+        self.fft.idft(Evaluations::new(evals, self.size()))
+    }
+
+    /// L_i(ζ) = ω^i Z_H(ζ) / n(ζ - ω^i)
+    pub(crate) fn evaluate_lagrange_basis(&self, i: usize, ζ: F) -> F {
+        lagrange_basis_at(self.size(), self.generator().pow(i as u64), ζ)
+    }
+
+    /// L_0(ζ), L_1(ζ), ..., L_n-1(ζ) with single inversion
+    pub(crate) fn evaluate_all_lagrange_coefficients(&self, ζ: F) -> Vec<F> {
+        let z_h = self.evaluate_vanishing(ζ);
+        if z_h.is_zero() {
+            // ζ is in domain
+            return self
+                .elements()
+                .map(|ω_i| if ω_i == ζ { F::one() } else { F::zero() })
+                .collect();
+        }
+        let mut denominators = self
+            .elements()
+            .map(|ω_i| self.size * (ζ - ω_i))
+            .collect::<Vec<_>>();
+        batch_inversion(&mut denominators);
+        self.elements()
+            .zip(denominators)
+            .map(|(ω_i, inv)| ω_i * z_h * inv)
+            .collect()
+    }
+}
+
+/// L_i(ζ) = ω^i Z_H(ζ) / n(ζ - ω^i) over n size domain without building it
+pub(crate) fn lagrange_basis_at<F: FftField>(n: usize, ω_i: F, ζ: F) -> F {
+    if ζ == ω_i {
+        return F::one();
+    }
+    let z_h = ζ.pow(n as u64) - F::one();
+    if z_h.is_zero() {
+        return F::zero();
+    }
+    ω_i * z_h * (F::from(n as u64) * (ζ - ω_i)).invert().unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::EvaluationDomain;
+    use crate::fft::FftStrategy;
+
+    use bls_12_381::Fr as Scalar;
+    use rand::rngs::OsRng;
+    use zkstd::common::{FftField, Group, PrimeField};
+
+    #[test]
+    fn vanishing_test() {
+        let domain = EvaluationDomain::<Scalar>::new(4);
+        let z_h = domain.vanishing_poly();
+        assert_eq!(domain.elements().count(), 16);
+        assert!(domain.elements().all(|ω_i| z_h.evaluate(ω_i).is_zero()));
+        assert!(domain
+            .elements()
+            .all(|ω_i| domain.evaluate_vanishing(ω_i).is_zero()));
+        let ζ = Scalar::random(OsRng);
+        assert_eq!(z_h.evaluate(ζ), domain.evaluate_vanishing(ζ));
+    }
+
+    #[test]
+    fn coset_vanishing_test() {
+        let domain = EvaluationDomain::<Scalar>::new(3);
+        let extended = domain.extended(2);
+        assert_eq!(extended.size(), 32);
+        let inverses = domain.coset_vanishing_inverses(&extended);
+        assert_eq!(inverses.len(), 4);
+        let g = Scalar::MULTIPLICATIVE_GENERATOR;
+        extended.elements().enumerate().for_each(|(i, x)| {
+            assert_eq!(
+                domain.evaluate_vanishing(g * x) * inverses[i % 4],
+                Scalar::one()
+            )
+        });
+    }
+
+    #[test]
+    fn mixed_domain_test() {
+        let strategy = FftStrategy::Radix4 {
+            parallel_threshold: 4,
+        };
+        let domain = EvaluationDomain::<Scalar>::with_size(12, strategy);
+        let ω = domain.generator();
+        assert_eq!(ω.pow(12), Scalar::one());
+        assert!([4, 6].iter().all(|d| ω.pow(*d) != Scalar::one()));
+        // extended generator is a root of domain generator
+        let extended = domain.extended(2);
+        assert_eq!(extended.size(), 48);
+        assert_eq!(extended.fft().strategy(), strategy);
+        assert_eq!(extended.generator().pow(4), ω);
+        let inverses = domain.coset_vanishing_inverses(&extended);
+        let g = Scalar::MULTIPLICATIVE_GENERATOR;
+        extended.elements().enumerate().for_each(|(i, x)| {
+            assert_eq!(
+                domain.evaluate_vanishing(g * x) * inverses[i % 4],
+                Scalar::one()
+            )
+        });
+        let ζ = Scalar::random(OsRng);
+        let coeffs = domain.evaluate_all_lagrange_coefficients(ζ);
+        (0..12).for_each(|i| assert_eq!(domain.lagrange_basis(i).evaluate(ζ), coeffs[i]));
+    }
+
+    #[test]
+    fn lagrange_basis_test() {
+        let domain = EvaluationDomain::<Scalar>::new(4);
+        let ζ = Scalar::random(OsRng);
+        let coeffs = domain.evaluate_all_lagrange_coefficients(ζ);
+        for (i, l_i) in coeffs.iter().enumerate() {
+            let basis = domain.lagrange_basis(i);
+            domain.elements().enumerate().for_each(|(j, ω_j)| {
+                assert_eq!(basis.evaluate(ω_j), Scalar::from((i == j) as u64))
+            });
+            assert_eq!(basis.evaluate(ζ), *l_i);
+            assert_eq!(domain.evaluate_lagrange_basis(i, ζ), *l_i);
+        }
+        assert_eq!(
+            coeffs.iter().fold(Scalar::zero(), |acc, l| acc + l),
+            Scalar::one()
+        );
+
+        // ζ in domain selects its own basis
+        let ω_3 = domain.generator().pow(3);
+        let coeffs = domain.evaluate_all_lagrange_coefficients(ω_3);
+        assert!(coeffs
+            .iter()
+            .enumerate()
+            .all(|(i, l)| *l == Scalar::from((i == 3) as u64)));
+        assert_eq!(domain.evaluate_lagrange_basis(3, ω_3), Scalar::one());
+        assert!(domain.evaluate_lagrange_basis(2, ω_3).is_zero());
+    }
+}
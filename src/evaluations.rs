@@ -0,0 +1,172 @@
+//! polynomial in evaluation form which can't be committed as coefficients
+use core::ops::{Add, Mul, Sub};
+use zkstd::common::FftField;
+
+use crate::{
+    fft::{root_of_unity, Fft},
+    inner_product::DensePolynomial,
+};
+
+// p(c), p(cω), ..., p(cω^n-1) over n size domain H or its coset cH
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct Evaluations<F: FftField> {
+    evals: Vec<F>,
+    // primitive n th root of unity ω
+    generator: F,
+    // c which is one on H itself
+    offset: F,
+}
+
+impl<F: FftField> Evaluations<F> {
+    /// evaluations over n = 2^k or n = 3 * 2^k size domain padded with zero
+    pub(crate) fn new(evals: Vec<F>, n: usize) -> Self {
+        Self::with_domain(evals, n, root_of_unity(n), F::one())
+    }
+
+    /// evaluations over coset g * H of n size domain padded with zero
+    pub(crate) fn coset(evals: Vec<F>, n: usize) -> Self {
+        Self::with_domain(evals, n, root_of_unity(n), F::MULTIPLICATIVE_GENERATOR)
+    }
+
+    /// evaluations over offset * <generator> of n points padded with zero
+    pub(crate) fn with_domain(mut evals: Vec<F>, n: usize, generator: F, offset: F) -> Self {
+        assert!(evals.len() <= n);
+        evals.resize(n, F::zero());
+        Self {
+            evals,
+            generator,
+            offset,
+        }
+    }
+
+    /// domain size
+    pub(crate) fn size(&self) -> usize {
+        self.evals.len()
+    }
+
+    /// primitive n th root of unity of domain
+    pub(crate) fn generator(&self) -> F {
+        self.generator
+    }
+
+    /// coset offset which is one on domain itself
+    pub(crate) fn offset(&self) -> F {
+        self.offset
+    }
+
+    pub(crate) fn evals(&self) -> &[F] {
+        &self.evals
+    }
+
+    pub(crate) fn into_evals(self) -> Vec<F> {
+        self.evals
+    }
+
+    pub(crate) fn interpolate(self, fft: &Fft<F>) -> DensePolynomial<F> {
+        fft.idft(self)
+    }
+
+    // apply f to every pair of evaluations at same point
+    fn pointwise(&self, rhs: &Self, f: impl Fn(F, F) -> F) -> Self {
+        assert!(
+            self.size() == rhs.size()
+                && self.generator == rhs.generator
+                && self.offset == rhs.offset,
+            "evaluations over different domains"
+        );
+        let evals = self
+            .evals
+            .iter()
+            .zip(rhs.evals.iter())
+            .map(|(a, b)| f(*a, *b))
+            .collect();
+        Self { evals, ..*self }
+    }
+}
+
+impl<F: FftField> Add<&Evaluations<F>> for &Evaluations<F> {
+    type Output = Evaluations<F>;
+
+    fn add(self, rhs: &Evaluations<F>) -> Evaluations<F> {
+        self.pointwise(rhs, |a, b| a + b)
+    }
+}
+
+impl<F: FftField> Sub<&Evaluations<F>> for &Evaluations<F> {
+    type Output = Evaluations<F>;
+
+    fn sub(self, rhs: &Evaluations<F>) -> Evaluations<F> {
+        self.pointwise(rhs, |a, b| a - b)
+    }
+}
+
+impl<F: FftField> Mul<&Evaluations<F>> for &Evaluations<F> {
+    type Output = Evaluations<F>;
+
+    fn mul(self, rhs: &Evaluations<F>) -> Evaluations<F> {
+        self.pointwise(rhs, |a, b| a * b)
+    }
+}
+
+impl<F: FftField> Mul<F> for &Evaluations<F> {
+    type Output = Evaluations<F>;
+
+    fn mul(self, scalar: F) -> Evaluations<F> {
+        let evals = self.evals.iter().map(|eval| *eval * scalar).collect();
+        Evaluations { evals, ..*self }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Evaluations;
+    use crate::{fft::Fft, inner_product::DensePolynomial};
+
+    use bls_12_381::Fr as Scalar;
+    use rand::rngs::OsRng;
+    use zkstd::common::Group;
+
+    #[test]
+    fn pointwise_arithmetic_test() {
+        let fft = Fft::new(5);
+        let a = DensePolynomial::<Scalar>::random(3);
+        let b = DensePolynomial::<Scalar>::random(4);
+        let (a_evals, b_evals) = (fft.dft(a.clone()), fft.dft(b.clone()));
+        let c = Scalar::random(OsRng);
+        let at = Scalar::random(OsRng);
+
+        let sum = (&a_evals + &b_evals).interpolate(&fft);
+        let difference = (&a_evals - &b_evals).interpolate(&fft);
+        let product = (&a_evals * &b_evals).interpolate(&fft);
+        let scaled = (&a_evals * c).interpolate(&fft);
+        assert_eq!(sum.evaluate(at), a.evaluate(at) + b.evaluate(at));
+        assert_eq!(difference.evaluate(at), a.evaluate(at) - b.evaluate(at));
+        assert_eq!(product.evaluate(at), a.evaluate(at) * b.evaluate(at));
+        assert_eq!(scaled.evaluate(at), a.evaluate(at) * c);
+    }
+
+    #[test]
+    fn padding_test() {
+        let evals = Evaluations::new(vec![Scalar::one(); 3], 8);
+        assert_eq!(evals.size(), 8);
+        assert!(evals.evals()[3..]
+            .iter()
+            .all(|eval| *eval == Scalar::zero()));
+    }
+
+    #[test]
+    #[should_panic(expected = "evaluations over different domains")]
+    fn domain_mismatch_test() {
+        let a = Evaluations::new(vec![Scalar::one(); 4], 4);
+        let b = Evaluations::new(vec![Scalar::one(); 4], 8);
+        let _ = &a + &b;
+    }
+
+    #[test]
+    #[should_panic(expected = "evaluations over different domains")]
+    fn coset_mismatch_test() {
+        let a = Evaluations::new(vec![Scalar::one(); 4], 4);
+        let b = Evaluations::coset(vec![Scalar::one(); 4], 4);
+        let _ = &a * &b;
+    }
+}
@@ -1,41 +1,79 @@
-use crate::inner_product::Polynomial;
+use crate::{evaluations::Evaluations, inner_product::DensePolynomial, utils::powers_of};
 
-use rayon::join;
+use core::ops::{AddAssign, MulAssign, SubAssign};
+use rayon::{join, prelude::*};
+use zkstd::behave::CurveExtended;
 use zkstd::common::FftField;
 
+mod radix3;
+mod radix4;
+
+use radix3::{interleave, split, Radix3};
+
+/// butterfly network used by fft
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum FftStrategy {
+    /// recursive radix-2 which joins both halves on rayon at every level
+    #[default]
+    Recursive,
+    /// iterative radix-4 which runs serially on chunks of up to threshold elements
+    Radix4 { parallel_threshold: usize },
+}
+
 pub struct Fft<F: FftField> {
     // domain size
     n: usize,
-    // n th root of unity
+    // butterfly network
+    strategy: FftStrategy,
+    // primitive n th root of unity
+    generator: F,
+    // outer stage when n = 3m
+    radix3: Option<Radix3<F>>,
+    // m th root of unity for power of two factor m of n
     twiddle_factors: Vec<F>,
-    // n th root of unity inverse
+    // m th root of unity inverse
     inv_twiddle_factors: Vec<F>,
     // n inverse
     n_inv: F,
-    // bit reverse index
+    // bit reverse index over m
     bit_reverse: Vec<(usize, usize)>,
 }
 
 impl<F: FftField> Fft<F> {
     pub fn new(k: usize) -> Self {
-        assert!(k >= 1);
-        let n = 1 << k;
-        let half_n = n / 2;
+        Self::with_strategy(k, FftStrategy::default())
+    }
+
+    pub fn with_strategy(k: usize, strategy: FftStrategy) -> Self {
+        Self::with_size(1 << k, strategy)
+    }
+
+    /// domain of n = 2^k or n = 3 * 2^k points
+    pub(crate) fn with_size(n: usize, strategy: FftStrategy) -> Self {
+        let g = root_of_unity::<F>(n);
+        let (m, radix3) = if n.is_power_of_two() {
+            (n, None)
+        } else {
+            (n / 3, Some(Radix3::new(g, n / 3)))
+        };
+        assert!(m >= 2);
+        let k = m.trailing_zeros();
+        let half_m = m / 2;
         let offset = 64 - k;
 
         // compute twiddle factors
-        let g = (0..F::S - k).fold(F::ROOT_OF_UNITY, |acc, _| acc.square());
-        let twiddle_factors = (0..half_n)
+        let g_m = g.pow((n / m) as u64);
+        let twiddle_factors = (0..half_m)
             .scan(F::one(), |w, _| {
                 let tw = *w;
-                *w *= g;
+                *w *= g_m;
                 Some(tw)
             })
             .collect::<Vec<_>>();
 
         // compute inverse twiddle factors
-        let g_inv = g.invert().unwrap();
-        let inv_twiddle_factors = (0..half_n)
+        let g_inv = g_m.invert().unwrap();
+        let inv_twiddle_factors = (0..half_m)
             .scan(F::one(), |w, _| {
                 let tw = *w;
                 *w *= g_inv;
@@ -43,7 +81,7 @@ impl<F: FftField> Fft<F> {
             })
             .collect::<Vec<_>>();
 
-        let bit_reverse = (0..n as u64)
+        let bit_reverse = (0..m as u64)
             .filter_map(|i| {
                 let r = i.reverse_bits() >> offset;
                 (i < r).then_some((i as usize, r as usize))
@@ -54,6 +92,9 @@ impl<F: FftField> Fft<F> {
 
         Self {
             n,
+            strategy,
+            generator: g,
+            radix3,
             twiddle_factors,
             inv_twiddle_factors,
             n_inv,
@@ -61,46 +102,234 @@ impl<F: FftField> Fft<F> {
         }
     }
 
-    /// perform discrete fourier transform
-    pub(crate) fn dft(&self, poly: &mut Polynomial<F>) {
-        self.prepare_fft(poly);
-        classic_fft_arithmetic(&mut poly.coeffs, self.n, 1, &self.twiddle_factors)
+    /// domain size
+    pub(crate) fn size(&self) -> usize {
+        self.n
+    }
+
+    /// butterfly network
+    pub(crate) fn strategy(&self) -> FftStrategy {
+        self.strategy
+    }
+
+    /// primitive n th root of unity
+    pub(crate) fn generator(&self) -> F {
+        self.generator
+    }
+
+    /// perform discrete fourier transform from coefficients to evaluations
+    pub(crate) fn dft(&self, poly: DensePolynomial<F>) -> Evaluations<F> {
+        let mut coeffs = poly.coeffs;
+        assert!(coeffs.len() <= self.n, "polynomial exceeds domain size");
+        self.evaluate_in_place(&mut coeffs);
+        Evaluations::with_domain(coeffs, self.n, self.generator, F::one())
+    }
+
+    /// perform inverse discrete fourier transform from evaluations to coefficients
+    pub(crate) fn idft(&self, evals: Evaluations<F>) -> DensePolynomial<F> {
+        self.idft_on(evals, F::one())
+    }
+
+    /// coefficients padded to n into evaluations over domain
+    pub fn evaluate_in_place(&self, coeffs: &mut Vec<F>) {
+        self.forward(coeffs, F::zero())
+    }
+
+    /// evaluations over domain into coefficients
+    pub fn interpolate_in_place(&self, evals: &mut Vec<F>) {
+        self.inverse(evals, F::zero());
+        evals.iter_mut().for_each(|eval| *eval *= self.n_inv)
     }
 
-    /// perform classic inverse discrete fourier transform
-    pub(crate) fn idft(&self, poly: &mut Polynomial<F>) {
-        self.prepare_fft(poly);
-        classic_fft_arithmetic(&mut poly.coeffs, self.n, 1, &self.inv_twiddle_factors);
-        poly.coeffs
-            .iter_mut()
-            .for_each(|coeff| *coeff *= self.n_inv)
+    /// evaluate on coset g * H where g is multiplicative generator
+    pub(crate) fn coset_dft(&self, mut poly: DensePolynomial<F>) -> Evaluations<F> {
+        let g = F::MULTIPLICATIVE_GENERATOR;
+        scale_by_powers(&mut poly.coeffs, g);
+        let evals = self.dft(poly).into_evals();
+        Evaluations::with_domain(evals, self.n, self.generator, g)
+    }
+
+    /// interpolate evaluations on coset g * H
+    pub(crate) fn coset_idft(&self, evals: Evaluations<F>) -> DensePolynomial<F> {
+        let g = F::MULTIPLICATIVE_GENERATOR;
+        let mut poly = self.idft_on(evals, g);
+        scale_by_powers(&mut poly.coeffs, g.invert().unwrap());
+        poly
+    }
+
+    /// perform inverse discrete fourier transform over curve points
+    pub(crate) fn idft_points<G: CurveExtended<Scalar = F>>(&self, points: &mut Vec<G>) {
+        self.inverse(points, G::ADDITIVE_IDENTITY);
+        points.iter_mut().for_each(|point| *point *= self.n_inv)
     }
 
     /// polynomial multiplication
-    pub(crate) fn poly_mul(&self, mut rhs: Polynomial<F>, mut lhs: Polynomial<F>) -> Polynomial<F> {
-        self.dft(&mut rhs);
-        self.dft(&mut lhs);
-        let mut mul_poly = Polynomial::new(
-            rhs.coeffs
-                .iter()
-                .zip(lhs.coeffs.iter())
-                .map(|(a, b)| *a * *b)
-                .collect(),
+    pub(crate) fn poly_mul(
+        &self,
+        rhs: DensePolynomial<F>,
+        lhs: DensePolynomial<F>,
+    ) -> DensePolynomial<F> {
+        let product = &self.dft(rhs) * &self.dft(lhs);
+        self.idft(product)
+    }
+
+    // interpolate evaluations on offset * H
+    fn idft_on(&self, evals: Evaluations<F>, offset: F) -> DensePolynomial<F> {
+        assert!(
+            evals.size() == self.n
+                && evals.generator() == self.generator
+                && evals.offset() == offset,
+            "evaluations over different domain"
         );
-        self.idft(&mut mul_poly);
-        mul_poly
+        let mut coeffs = evals.into_evals();
+        self.interpolate_in_place(&mut coeffs);
+        DensePolynomial { coeffs }
     }
 
-    fn prepare_fft(&self, poly: &mut Polynomial<F>) {
-        poly.coeffs.resize(self.n, F::zero());
+    // natural order input padded to n into natural order output
+    fn forward<T: Copy + Send + Sync + AddAssign + SubAssign + MulAssign<F>>(
+        &self,
+        values: &mut Vec<T>,
+        zero: T,
+    ) {
+        values.resize(self.n, zero);
+        match &self.radix3 {
+            None => self.radix2(values, &self.twiddle_factors),
+            Some(radix3) => {
+                let mut parts = split(values);
+                parts
+                    .par_iter_mut()
+                    .for_each(|part| self.radix2(part, &self.twiddle_factors));
+                radix3.merge(&parts, values)
+            }
+        }
+    }
+
+    // forward with inverse twiddles and without scaling by n inverse
+    fn inverse<T: Copy + Send + Sync + AddAssign + SubAssign + MulAssign<F>>(
+        &self,
+        values: &mut Vec<T>,
+        zero: T,
+    ) {
+        values.resize(self.n, zero);
+        match &self.radix3 {
+            None => self.radix2(values, &self.inv_twiddle_factors),
+            Some(radix3) => {
+                let mut parts = radix3.unmerge(values);
+                parts
+                    .par_iter_mut()
+                    .for_each(|part| self.radix2(part, &self.inv_twiddle_factors));
+                interleave(&parts, values)
+            }
+        }
+    }
+
+    // m point fft from natural order to natural order
+    fn radix2<T: Copy + Send + Sync + AddAssign + SubAssign + MulAssign<F>>(
+        &self,
+        values: &mut [T],
+        twiddles: &[F],
+    ) {
         self.bit_reverse
             .iter()
-            .for_each(|(i, ri)| poly.coeffs.swap(*ri, *i));
+            .for_each(|(i, ri)| values.swap(*ri, *i));
+        self.butterflies(values, twiddles)
+    }
+
+    // bit reversed input to natural order output
+    fn butterflies<T: Copy + Send + Sync + AddAssign + SubAssign + MulAssign<F>>(
+        &self,
+        values: &mut [T],
+        twiddles: &[F],
+    ) {
+        match self.strategy {
+            FftStrategy::Recursive => classic_fft_arithmetic(values, values.len(), 1, twiddles),
+            FftStrategy::Radix4 { parallel_threshold } => {
+                radix4::radix4_arithmetic(values, twiddles, parallel_threshold)
+            }
+        }
     }
 }
 
-fn classic_fft_arithmetic<F: FftField>(
-    coeffs: &mut [F],
+/// whether F has a subgroup of order n = 2^k or n = 3 * 2^k
+pub(crate) fn is_domain_size<F: FftField>(n: usize) -> bool {
+    let m = if n.is_multiple_of(3) { n / 3 } else { n };
+    m.is_power_of_two()
+        && m.trailing_zeros() as usize <= F::S
+        && (m == n || cube_root_of_unity::<F>().is_some())
+}
+
+/// primitive n th root of unity for n = 2^k or n = 3 * 2^k
+/// ω^3 is the 2^k th root of unity so that domains of both kinds nest
+pub(crate) fn root_of_unity<F: FftField>(n: usize) -> F {
+    let m = if n.is_multiple_of(3) { n / 3 } else { n };
+    let k = m.trailing_zeros() as usize;
+    assert!(
+        m.is_power_of_two() && k <= F::S,
+        "multiplicative group has no subgroup of order {n}"
+    );
+    let ω = (k..F::S).fold(F::ROOT_OF_UNITY, |acc, _| acc.square());
+    if m == n {
+        return ω;
+    }
+    let ρ = cube_root_of_unity::<F>()
+        .unwrap_or_else(|| panic!("multiplicative group has no subgroup of order {n}"));
+    // t = 1 / 3 mod 2^k so that (ρ ω^t)^3 = ω
+    let t = (if k.is_multiple_of(2) {
+        2 * m + 1
+    } else {
+        m + 1
+    }) / 3;
+    ρ * ω.pow(t as u64)
+}
+
+/// primitive cube root of unity h^((p - 1) / 3) if 3 divides p - 1
+pub(crate) fn cube_root_of_unity<F: FftField>() -> Option<F> {
+    // p - 1 in big endian bits divided by 3 with long division
+    let mut remainder = 0;
+    let quotient = (-F::one())
+        .to_bits()
+        .into_iter()
+        .map(|bit| {
+            remainder = remainder * 2 + bit;
+            let q = remainder >= 3;
+            if q {
+                remainder -= 3;
+            }
+            q
+        })
+        .collect::<Vec<_>>();
+    if remainder != 0 {
+        return None;
+    }
+    // h^((p - 1) / 3) is one exactly when h is a cube
+    (2..)
+        .map(|h: u64| {
+            let h = F::from(h);
+            quotient.iter().fold(F::one(), |acc, bit| {
+                let acc = acc.square();
+                if *bit {
+                    acc * h
+                } else {
+                    acc
+                }
+            })
+        })
+        .find(|ρ| *ρ != F::one())
+}
+
+// c_i * g^i so that p(g * x) is transformed instead of p(x)
+fn scale_by_powers<F: FftField>(coeffs: &mut [F], g: F) {
+    let powers = powers_of(g, coeffs.len());
+    coeffs
+        .iter_mut()
+        .zip(powers)
+        .for_each(|(coeff, power)| *coeff *= power)
+}
+
+// butterflies only need addition and scaling by twiddle factors so coeffs can be field or group elements
+fn classic_fft_arithmetic<F: FftField, T: Copy + Send + AddAssign + SubAssign + MulAssign<F>>(
+    coeffs: &mut [T],
     n: usize,
     twiddle_chunk: usize,
     twiddles: &[F],
@@ -120,9 +349,9 @@ fn classic_fft_arithmetic<F: FftField>(
     }
 }
 
-fn butterfly_arithmetic<F: FftField>(
-    left: &mut [F],
-    right: &mut [F],
+fn butterfly_arithmetic<F: FftField, T: Copy + AddAssign + SubAssign + MulAssign<F>>(
+    left: &mut [T],
+    right: &mut [T],
     twiddle_chunk: usize,
     twiddles: &[F],
 ) {
@@ -149,12 +378,12 @@ fn butterfly_arithmetic<F: FftField>(
 mod tests {
     use super::*;
 
-    use bls_12_381::Fr as Scalar;
+    use bls_12_381::{Fr as Scalar, G1Projective};
     use rand::rngs::OsRng;
-    use zkstd::behave::{Group, PrimeField};
+    use zkstd::behave::{CurveGroup, Group, PrimeField};
 
-    fn arb_poly(k: u32) -> Polynomial<Scalar> {
-        Polynomial {
+    fn arb_poly(k: u32) -> DensePolynomial<Scalar> {
+        DensePolynomial {
             coeffs: (0..(1 << k))
                 .map(|_| Scalar::random(OsRng))
                 .collect::<Vec<_>>(),
@@ -172,37 +401,22 @@ mod tests {
         c
     }
 
-    fn point_mutiply<F: FftField>(a: Polynomial<F>, b: Polynomial<F>) -> Polynomial<F> {
-        assert_eq!(a.coeffs.len(), b.coeffs.len());
-        Polynomial {
-            coeffs: a
-                .coeffs
-                .iter()
-                .zip(b.coeffs.iter())
-                .map(|(coeff_a, coeff_b)| *coeff_a * *coeff_b)
-                .collect::<Vec<F>>(),
-        }
-    }
-
-    fn coeffs_to_evals<F: FftField>(a: Polynomial<F>) -> Polynomial<F> {
-        Polynomial {
-            coeffs: (0..a.coeffs.len())
-                .map(|i| {
-                    let at = F::from(i as u64);
-                    a.evaluate(at)
-                })
-                .collect(),
-        }
+    fn coeffs_to_evals<F: FftField>(a: DensePolynomial<F>) -> Vec<F> {
+        (0..a.coeffs.len())
+            .map(|i| {
+                let at = F::from(i as u64);
+                a.evaluate(at)
+            })
+            .collect()
     }
 
     #[test]
     fn fft_transformation_test() {
-        let mut poly_a = arb_poly(10);
-        let poly_b = poly_a.clone();
+        let poly_b = arb_poly(10);
         let classic_fft = Fft::new(10);
 
-        classic_fft.dft(&mut poly_a);
-        classic_fft.idft(&mut poly_a);
+        let evals = classic_fft.dft(poly_b.clone());
+        let poly_a = classic_fft.idft(evals);
 
         assert_eq!(poly_a, poly_b)
     }
@@ -214,19 +428,16 @@ mod tests {
         let fft = Fft::new(5);
         let poly_c = coeffs_a.clone();
         let poly_d = coeffs_b.clone();
-        let mut poly_a = coeffs_a;
-        let mut poly_b = coeffs_b;
-        let poly_g = poly_a.clone();
-        let poly_h = poly_b.clone();
+        let poly_g = coeffs_a.clone();
+        let poly_h = coeffs_b.clone();
 
-        let poly_e = Polynomial {
+        let poly_e = DensePolynomial {
             coeffs: naive_multiply(poly_c.coeffs, poly_d.coeffs),
         };
 
-        fft.dft(&mut poly_a);
-        fft.dft(&mut poly_b);
-        let mut poly_f = point_mutiply(poly_a, poly_b);
-        fft.idft(&mut poly_f);
+        let evals_a = fft.dft(coeffs_a);
+        let evals_b = fft.dft(coeffs_b);
+        let poly_f = fft.idft(&evals_a * &evals_b);
 
         let poly_i = fft.poly_mul(poly_g, poly_h);
 
@@ -241,11 +452,119 @@ mod tests {
         let evals_a = coeffs_to_evals(arb_poly(k));
         let evals_b = coeffs_to_evals(arb_poly(k));
         let fft = Fft::new((k + 1) as usize);
-        let mut evals_a_prime = evals_a.clone();
-        let mut evals_b_prime = evals_b.clone();
-        fft.idft(&mut evals_a_prime);
-        fft.idft(&mut evals_b_prime);
+        let evals_a_prime = fft.idft(Evaluations::new(evals_a.clone(), 1 << (k + 1)));
+        let evals_b_prime = fft.idft(Evaluations::new(evals_b.clone(), 1 << (k + 1)));
+
+        assert!((0..n).all(|i| evals_a_prime.evaluate(fft.twiddle_factors[i]) == evals_a[i]));
+        assert!((0..n).all(|i| evals_b_prime.evaluate(fft.twiddle_factors[i]) == evals_b[i]));
+    }
+
+    #[test]
+    fn coset_fft_test() {
+        let poly = arb_poly(4);
+        let fft = Fft::new(5);
+        let evals = fft.coset_dft(poly.clone());
+        let g = Scalar::MULTIPLICATIVE_GENERATOR;
+        assert_eq!(evals.offset(), g);
+        assert!(evals
+            .evals()
+            .iter()
+            .zip(powers_of(fft.generator(), 32))
+            .all(|(eval, x)| poly.evaluate(g * x) == *eval));
+
+        let mut coeffs = fft.coset_idft(evals);
+        coeffs.coeffs.truncate(16);
+        assert_eq!(coeffs, poly)
+    }
 
-        assert!((0..n).all(|i| evals_a_prime.evaluate(fft.twiddle_factors[i]) == evals_a.coeffs[i]));
+    #[test]
+    fn radix4_fft_test() {
+        for k in 1..=10 {
+            let recursive = Fft::with_strategy(k, FftStrategy::Recursive);
+            let poly = arb_poly(k as u32);
+            let expected = recursive.dft(poly.clone());
+            for parallel_threshold in [0, 1, 4, 16, 1 << 20, usize::MAX] {
+                let radix4 = Fft::with_strategy(k, FftStrategy::Radix4 { parallel_threshold });
+                let evals = radix4.dft(poly.clone());
+                assert_eq!(evals, expected);
+                assert_eq!(radix4.idft(evals), poly);
+            }
+        }
+
+        // group elements go through same butterflies
+        for k in 1..=5 {
+            let recursive = Fft::<Scalar>::with_strategy(k, FftStrategy::Recursive);
+            let radix4 = Fft::with_strategy(
+                k,
+                FftStrategy::Radix4 {
+                    parallel_threshold: 4,
+                },
+            );
+            let mut points = (0..1 << k)
+                .map(|_| G1Projective::random(OsRng))
+                .collect::<Vec<_>>();
+            let mut expected = points.clone();
+            recursive.idft_points(&mut expected);
+            radix4.idft_points(&mut points);
+            assert_eq!(points, expected);
+        }
+    }
+
+    #[test]
+    fn mixed_radix_test() {
+        for k in 1..=6 {
+            let n = 3 << k;
+            let fft = Fft::<Scalar>::with_size(n, FftStrategy::Recursive);
+            let ω = fft.generator();
+            assert_eq!(ω.pow(n as u64), Scalar::one());
+            assert!([n / 2, n / 3]
+                .iter()
+                .all(|d| ω.pow(*d as u64) != Scalar::one()));
+            assert_eq!(ω.pow(3), root_of_unity(1 << k));
+
+            let poly = DensePolynomial::new((0..n).map(|_| Scalar::random(OsRng)).collect());
+            let evals = fft.dft(poly.clone());
+            assert!(evals
+                .evals()
+                .iter()
+                .zip(powers_of(ω, n))
+                .all(|(eval, x)| poly.evaluate(x) == *eval));
+            let radix4 = Fft::with_size(
+                n,
+                FftStrategy::Radix4 {
+                    parallel_threshold: 4,
+                },
+            );
+            assert_eq!(radix4.dft(poly.clone()), evals);
+            assert_eq!(fft.idft(evals), poly);
+            assert_eq!(fft.coset_idft(fft.coset_dft(poly.clone())), poly);
+        }
+
+        assert!([8, 12, 3 << 32]
+            .iter()
+            .all(|n| is_domain_size::<Scalar>(*n)));
+        assert!(![0, 9, 40, 1 << 33]
+            .iter()
+            .any(|n| is_domain_size::<Scalar>(*n)));
+
+        // lagrange basis points are interpolated over same domain
+        let fft = Fft::<Scalar>::with_size(12, FftStrategy::Recursive);
+        let scalars = (0..12).map(|_| Scalar::random(OsRng)).collect::<Vec<_>>();
+        let mut points = scalars
+            .iter()
+            .map(|s| G1Projective::ADDITIVE_GENERATOR * *s)
+            .collect::<Vec<_>>();
+        fft.idft_points(&mut points);
+        let coeffs = fft.idft(Evaluations::new(scalars, 12)).coeffs;
+        assert!(points
+            .iter()
+            .zip(coeffs)
+            .all(|(point, coeff)| *point == G1Projective::ADDITIVE_GENERATOR * coeff));
+    }
+
+    #[test]
+    #[should_panic(expected = "multiplicative group has no subgroup of order 40")]
+    fn unsupported_size_test() {
+        Fft::<Scalar>::with_size(40, FftStrategy::Recursive);
     }
 }
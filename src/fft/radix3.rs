@@ -0,0 +1,110 @@
+//! radix-3 stage joining three interleaved power of two ffts over 3 * 2^k points
+use core::ops::{AddAssign, MulAssign, SubAssign};
+use rayon::prelude::*;
+use zkstd::common::FftField;
+
+pub(super) struct Radix3<F: FftField> {
+    // ω^i and ω^-i for i < 2m where n = 3m
+    twiddles: Vec<F>,
+    inv_twiddles: Vec<F>,
+    // -1 / 2
+    neg_half: F,
+    // (ρ - ρ^2) / 2 for cube root ρ = ω^m
+    skew: F,
+}
+
+impl<F: FftField> Radix3<F> {
+    pub(super) fn new(generator: F, m: usize) -> Self {
+        let powers = |g: F| {
+            (0..2 * m)
+                .scan(F::one(), |w, _| {
+                    let tw = *w;
+                    *w *= g;
+                    Some(tw)
+                })
+                .collect::<Vec<_>>()
+        };
+        let ρ = generator.pow(m as u64);
+        let half = F::from(2).invert().unwrap();
+        Self {
+            twiddles: powers(generator),
+            inv_twiddles: powers(generator.invert().unwrap()),
+            neg_half: -half,
+            skew: (ρ - ρ.square()) * half,
+        }
+    }
+
+    /// X_s+rm = A_0(s) + ρ^r ω^s A_1(s) + ρ^2r ω^2s A_2(s) from m point dfts A_j of split parts
+    pub(super) fn merge<T>(&self, parts: &[Vec<T>; 3], values: &mut [T])
+    where
+        T: Copy + Send + Sync + AddAssign + SubAssign + MulAssign<F>,
+    {
+        let m = parts[0].len();
+        let (x0, rest) = values.split_at_mut(m);
+        let (x1, x2) = rest.split_at_mut(m);
+        x0.par_iter_mut()
+            .zip(x1.par_iter_mut())
+            .zip(x2.par_iter_mut())
+            .enumerate()
+            .for_each(|(s, ((x0, x1), x2))| {
+                let (mut b1, mut b2) = (parts[1][s], parts[2][s]);
+                b1 *= self.twiddles[s];
+                b2 *= self.twiddles[2 * s];
+                (*x0, *x1, *x2) = self.butterfly(parts[0][s], b1, b2, self.skew);
+            })
+    }
+
+    /// inverse of merge up to factor 3 which is left to the caller
+    pub(super) fn unmerge<T>(&self, values: &[T]) -> [Vec<T>; 3]
+    where
+        T: Copy + Send + Sync + AddAssign + SubAssign + MulAssign<F>,
+    {
+        let m = values.len() / 3;
+        // ρ^-1 - ρ^-2 = ρ^2 - ρ
+        let (y0, (y1, y2)) = (0..m)
+            .into_par_iter()
+            .map(|s| {
+                let (y0, mut y1, mut y2) =
+                    self.butterfly(values[s], values[s + m], values[s + 2 * m], -self.skew);
+                y1 *= self.inv_twiddles[s];
+                y2 *= self.inv_twiddles[2 * s];
+                (y0, (y1, y2))
+            })
+            .unzip();
+        [y0, y1, y2]
+    }
+
+    // 3 point dft a + ρ^r b + ρ^2r c with ρ + ρ^2 = -1 so that it takes two multiplications
+    fn butterfly<T>(&self, a: T, b: T, c: T, skew: F) -> (T, T, T)
+    where
+        T: Copy + AddAssign + SubAssign + MulAssign<F>,
+    {
+        let (mut u, mut v) = (b, b);
+        u += c;
+        v -= c;
+        let (mut x0, mut x1) = (a, a);
+        x0 += u;
+        u *= self.neg_half;
+        v *= skew;
+        x1 += u;
+        let mut x2 = x1;
+        x1 += v;
+        x2 -= v;
+        (x0, x1, x2)
+    }
+}
+
+/// c_3i+j into j th part
+pub(super) fn split<T: Copy>(values: &[T]) -> [Vec<T>; 3] {
+    [0, 1, 2].map(|j| values.iter().skip(j).step_by(3).copied().collect())
+}
+
+/// inverse of split
+pub(super) fn interleave<T: Copy>(parts: &[Vec<T>; 3], values: &mut [T]) {
+    values.chunks_mut(3).enumerate().for_each(|(i, chunk)| {
+        chunk
+            .iter_mut()
+            .zip(parts.iter())
+            .for_each(|(value, part)| *value = part[i])
+    })
+}
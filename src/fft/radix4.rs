@@ -0,0 +1,106 @@
+//! iterative radix-4 butterflies fusing two radix-2 stages per pass
+use core::ops::{AddAssign, MulAssign, SubAssign};
+use rayon::prelude::*;
+use zkstd::common::FftField;
+
+/// bit reversed input to natural order output where twiddles are ω^0, ..., ω^n/2-1
+/// passes over blocks of up to threshold run chunk by chunk so that each chunk stays in cache
+pub(super) fn radix4_arithmetic<F, T>(values: &mut [T], twiddles: &[F], threshold: usize)
+where
+    F: FftField,
+    T: Copy + Send + Sync + AddAssign + SubAssign + MulAssign<F>,
+{
+    let n = values.len();
+    // radix-2 pass first when log n is odd
+    let first = if n.trailing_zeros() % 2 == 1 { 2 } else { 4 };
+    let blocks = (0..)
+        .map(|i| first << (2 * i))
+        .take_while(|block| *block <= n)
+        .collect::<Vec<usize>>();
+    let chunk = threshold.min(n).next_power_of_two().max(4).min(n);
+    let (small, large) = blocks.split_at(blocks.partition_point(|block| *block <= chunk));
+
+    let small_passes = |values: &mut [T]| {
+        small
+            .iter()
+            .for_each(|block| values.chunks_mut(*block).for_each(|b| pass(b, n, twiddles)))
+    };
+    if n <= threshold {
+        small_passes(values)
+    } else {
+        values.par_chunks_mut(chunk).for_each(small_passes);
+    }
+
+    // fewer blocks than chunks so split butterflies of each block instead
+    large.iter().for_each(|block| {
+        let quarter = block / 4;
+        let span = (chunk / 4).max(1);
+        values.chunks_mut(*block).for_each(|b| {
+            let (q01, q23) = b.split_at_mut(2 * quarter);
+            let (q0, q1) = q01.split_at_mut(quarter);
+            let (q2, q3) = q23.split_at_mut(quarter);
+            q0.par_chunks_mut(span)
+                .zip(q1.par_chunks_mut(span))
+                .zip(q2.par_chunks_mut(span))
+                .zip(q3.par_chunks_mut(span))
+                .enumerate()
+                .for_each(|(i, (((q0, q1), q2), q3))| {
+                    butterflies([q0, q1, q2, q3], i * span, n / block, n, twiddles)
+                })
+        })
+    })
+}
+
+// one pass over a block of size 2 or 4m
+fn pass<F, T>(block: &mut [T], n: usize, twiddles: &[F])
+where
+    F: FftField,
+    T: Copy + AddAssign + SubAssign + MulAssign<F>,
+{
+    if block.len() == 2 {
+        let t = block[1];
+        block[1] = block[0];
+        block[0] += t;
+        block[1] -= t;
+    } else {
+        let quarter = block.len() / 4;
+        let stride = n / block.len();
+        let (q01, q23) = block.split_at_mut(2 * quarter);
+        let (q0, q1) = q01.split_at_mut(quarter);
+        let (q2, q3) = q23.split_at_mut(quarter);
+        butterflies([q0, q1, q2, q3], 0, stride, n, twiddles)
+    }
+}
+
+// radix-2 stages of size 2m on (q0, q1), (q2, q3) and of size 4m on (q0, q2), (q1, q3)
+// ω_2m^j = ω^2jstride, ω_4m^j = ω^jstride and ω_4m^(j + m) = ω^(jstride + n/4)
+fn butterflies<F, T>(
+    [q0, q1, q2, q3]: [&mut [T]; 4],
+    offset: usize,
+    stride: usize,
+    n: usize,
+    twiddles: &[F],
+) where
+    F: FftField,
+    T: Copy + AddAssign + SubAssign + MulAssign<F>,
+{
+    (0..q0.len()).for_each(|i| {
+        let j = (offset + i) * stride;
+        let (w1, w2, w3) = (twiddles[2 * j], twiddles[j], twiddles[j + n / 4]);
+        let (mut t1, mut t3) = (q1[i], q3[i]);
+        t1 *= w1;
+        t3 *= w1;
+        let (mut b0, mut b1, mut b2, mut b3) = (q0[i], q0[i], q2[i], q2[i]);
+        b0 += t1;
+        b1 -= t1;
+        b2 += t3;
+        b3 -= t3;
+        b2 *= w2;
+        b3 *= w3;
+        (q0[i], q1[i], q2[i], q3[i]) = (b0, b1, b0, b1);
+        q0[i] += b2;
+        q2[i] -= b2;
+        q1[i] += b3;
+        q3[i] -= b3;
+    })
+}
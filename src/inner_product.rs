@@ -1,15 +1,20 @@
+use core::ops::{Add, AddAssign, Mul, Neg, Sub};
 use rand::rngs::OsRng;
 use zkstd::common::FftField;
 
-use crate::fft::Fft;
+use crate::{fft::Fft, utils::batch_inversion};
+
+mod subproduct;
+
+use subproduct::SubproductTree;
 
 // first to last, x^0 to x^n-1
 #[derive(Clone, Debug, PartialEq, Eq)]
-pub(crate) struct Polynomial<F: FftField> {
+pub(crate) struct DensePolynomial<F: FftField> {
     pub(crate) coeffs: Vec<F>,
 }
 
-impl<F: FftField> Polynomial<F> {
+impl<F: FftField> DensePolynomial<F> {
     pub(crate) fn new(coeffs: Vec<F>) -> Self {
         Self { coeffs }
     }
@@ -43,11 +48,6 @@ impl<F: FftField> Polynomial<F> {
         )
     }
 
-    pub(crate) fn scalar(self, scalar: F) -> Self {
-        let coeffs = self.coeffs.iter().map(|coeff| *coeff * scalar).collect();
-        Self { coeffs }
-    }
-
     pub(crate) fn evaluate(&self, at: F) -> F {
         let mut acc = F::one();
         self.coeffs.iter().fold(F::zero(), |sum, coeff| {
@@ -73,41 +73,266 @@ impl<F: FftField> Polynomial<F> {
         Self { coeffs }
     }
 
-    pub(crate) fn from_evals(evals: Vec<F>, fft: Fft<F>) -> Self {
-        let mut coeffs = Self { coeffs: evals };
-        fft.idft(&mut coeffs);
-        coeffs
+    /// quotient and remainder by x^n - 1 in O(n)
+    pub(crate) fn divide_by_vanishing(&self, n: usize) -> (Self, Self) {
+        assert!(n > 0);
+        let mut remainder = self.coeffs.clone();
+        let mut quotient = vec![F::zero(); remainder.len().saturating_sub(n)];
+        // x^i = x^i-n (x^n - 1) + x^i-n from highest degree
+        for i in (n..remainder.len()).rev() {
+            let coeff = remainder[i];
+            quotient[i - n] = coeff;
+            remainder[i - n] += coeff;
+        }
+        remainder.truncate(n);
+        (Self::trimmed(quotient), Self::trimmed(remainder))
+    }
+
+    /// quotient and remainder by long division for short quotient and newton iteration otherwise
+    pub(crate) fn div_rem(&self, divisor: &Self) -> (Self, Self) {
+        let divisor = Self::trimmed(divisor.coeffs.clone());
+        let leading = divisor.coeffs.last().expect("division by zero polynomial");
+        let leading_inv = leading.invert().unwrap();
+        let d = divisor.coeffs.len() - 1;
+        let mut remainder = Self::trimmed(self.coeffs.clone()).coeffs;
+        if remainder.len() <= d {
+            return (Self::new(Vec::new()), Self::new(remainder));
+        }
+        if (remainder.len() - d).min(divisor.coeffs.len()) >= SCHOOLBOOK_THRESHOLD {
+            return Self::new(remainder).fast_div_rem(&divisor);
+        }
+        let mut quotient = vec![F::zero(); remainder.len() - d];
+        for i in (0..quotient.len()).rev() {
+            let coeff = remainder[i + d] * leading_inv;
+            quotient[i] = coeff;
+            remainder[i..=i + d]
+                .iter_mut()
+                .zip(divisor.coeffs.iter())
+                .for_each(|(r, c)| *r -= coeff * c);
+        }
+        remainder.truncate(d);
+        (Self::trimmed(quotient), Self::trimmed(remainder))
+    }
+
+    // rev(q) = rev(a) / rev(b) mod x^(deg a - deg b + 1) where rev(b) has constant term
+    fn fast_div_rem(&self, divisor: &Self) -> (Self, Self) {
+        let quotient_len = self.coeffs.len() - divisor.coeffs.len() + 1;
+        let reversed = |poly: &Self| Self::new(poly.coeffs.iter().rev().copied().collect());
+        let divisor_inv = reversed(divisor).inverse_series(quotient_len);
+        let mut quotient = (&reversed(self).truncated(quotient_len) * &divisor_inv)
+            .truncated(quotient_len)
+            .coeffs;
+        quotient.resize(quotient_len, F::zero());
+        quotient.reverse();
+        let quotient = Self::trimmed(quotient);
+        let remainder = self - &(divisor * &quotient);
+        (quotient, remainder)
+    }
+
+    // g with f * g = 1 mod x^k by newton iteration g' = g * (2 - f * g)
+    fn inverse_series(&self, k: usize) -> Self {
+        let mut inverse = Self::new(vec![self.coeffs[0].invert().unwrap()]);
+        let mut precision = 1;
+        while precision < k {
+            precision = (precision * 2).min(k);
+            let mut error = -(&self.truncated(precision) * &inverse).truncated(precision);
+            match error.coeffs.first_mut() {
+                Some(constant) => *constant += F::from(2u64),
+                None => error.coeffs.push(F::from(2u64)),
+            }
+            inverse = (&inverse * &error).truncated(precision);
+        }
+        inverse
+    }
+
+    // mod x^k
+    fn truncated(&self, k: usize) -> Self {
+        Self::trimmed(self.coeffs.iter().take(k).copied().collect())
+    }
+
+    /// formal derivative
+    pub(crate) fn derivative(&self) -> Self {
+        let coeffs = self
+            .coeffs
+            .iter()
+            .enumerate()
+            .skip(1)
+            .map(|(i, coeff)| F::from(i as u64) * *coeff)
+            .collect();
+        Self::trimmed(coeffs)
+    }
+
+    /// p(x_0), p(x_1), ..., p(x_n-1) with subproduct tree in O(n log^2 n)
+    pub(crate) fn evaluate_many(&self, points: &[F]) -> Vec<F> {
+        if points.is_empty() {
+            return Vec::new();
+        }
+        SubproductTree::new(points).evaluate(self)
+    }
+
+    /// lowest degree polynomial through (x_i, y_i) with subproduct tree in O(n log^2 n)
+    /// p(x) = Σ y_i / m'(x_i) * m(x) / (x - x_i) where m(x) = ∏(x - x_i)
+    pub(crate) fn interpolate(points: &[(F, F)]) -> Self {
+        if points.is_empty() {
+            return Self::new(Vec::new());
+        }
+        let xs = points.iter().map(|(x, _)| *x).collect::<Vec<_>>();
+        let tree = SubproductTree::new(&xs);
+        let mut weights = tree.evaluate(&tree.root().derivative());
+        assert!(
+            weights.iter().all(|weight| !weight.is_zero()),
+            "interpolation points must be distinct"
+        );
+        batch_inversion(&mut weights);
+        weights
+            .iter_mut()
+            .zip(points.iter())
+            .for_each(|(weight, (_, y))| *weight *= y);
+        tree.linear_combination(&weights)
+    }
+
+    // without leading zero coefficients
+    pub(crate) fn trimmed(mut coeffs: Vec<F>) -> Self {
+        while coeffs.last() == Some(&F::zero()) {
+            coeffs.pop();
+        }
+        Self { coeffs }
+    }
+}
+
+// multiplication below this length is done by schoolbook
+const SCHOOLBOOK_THRESHOLD: usize = 32;
+
+impl<F: FftField> Add<&DensePolynomial<F>> for &DensePolynomial<F> {
+    type Output = DensePolynomial<F>;
+
+    fn add(self, rhs: &DensePolynomial<F>) -> DensePolynomial<F> {
+        let mut sum = self.clone();
+        sum += rhs;
+        sum
+    }
+}
+
+impl<F: FftField> Add for DensePolynomial<F> {
+    type Output = Self;
+
+    fn add(mut self, rhs: Self) -> Self {
+        self += &rhs;
+        self
+    }
+}
+
+impl<F: FftField> AddAssign<&DensePolynomial<F>> for DensePolynomial<F> {
+    fn add_assign(&mut self, rhs: &DensePolynomial<F>) {
+        if self.coeffs.len() < rhs.coeffs.len() {
+            self.coeffs.resize(rhs.coeffs.len(), F::zero());
+        }
+        self.coeffs
+            .iter_mut()
+            .zip(rhs.coeffs.iter())
+            .for_each(|(a, b)| *a += *b);
+        *self = Self::trimmed(core::mem::take(&mut self.coeffs));
+    }
+}
+
+impl<F: FftField> Neg for &DensePolynomial<F> {
+    type Output = DensePolynomial<F>;
+
+    fn neg(self) -> DensePolynomial<F> {
+        DensePolynomial::new(self.coeffs.iter().map(|coeff| -*coeff).collect())
+    }
+}
+
+impl<F: FftField> Neg for DensePolynomial<F> {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        -&self
+    }
+}
+
+impl<F: FftField> Sub<&DensePolynomial<F>> for &DensePolynomial<F> {
+    type Output = DensePolynomial<F>;
+
+    fn sub(self, rhs: &DensePolynomial<F>) -> DensePolynomial<F> {
+        self + &-rhs
+    }
+}
+
+impl<F: FftField> Sub for DensePolynomial<F> {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self {
+        &self - &rhs
+    }
+}
+
+impl<F: FftField> Mul<F> for &DensePolynomial<F> {
+    type Output = DensePolynomial<F>;
+
+    fn mul(self, scalar: F) -> DensePolynomial<F> {
+        let coeffs = self.coeffs.iter().map(|coeff| *coeff * scalar).collect();
+        DensePolynomial::trimmed(coeffs)
+    }
+}
+
+impl<F: FftField> Mul<F> for DensePolynomial<F> {
+    type Output = Self;
+
+    fn mul(self, scalar: F) -> Self {
+        &self * scalar
+    }
+}
+
+impl<F: FftField> Mul<&DensePolynomial<F>> for &DensePolynomial<F> {
+    type Output = DensePolynomial<F>;
+
+    /// schoolbook for short polynomials and fft otherwise
+    fn mul(self, rhs: &DensePolynomial<F>) -> DensePolynomial<F> {
+        if self.coeffs.is_empty() || rhs.coeffs.is_empty() {
+            return DensePolynomial::new(Vec::new());
+        }
+        let size = self.coeffs.len() + rhs.coeffs.len() - 1;
+        if self.coeffs.len().min(rhs.coeffs.len()) < SCHOOLBOOK_THRESHOLD {
+            let mut coeffs = vec![F::zero(); size];
+            self.coeffs.iter().enumerate().for_each(|(i, a)| {
+                rhs.coeffs
+                    .iter()
+                    .enumerate()
+                    .for_each(|(j, b)| coeffs[i + j] += *a * *b)
+            });
+            DensePolynomial::trimmed(coeffs)
+        } else {
+            let fft = Fft::new(size.next_power_of_two().trailing_zeros() as usize);
+            let product = fft.poly_mul(self.clone(), rhs.clone());
+            DensePolynomial::trimmed(product.coeffs)
+        }
+    }
+}
+
+impl<F: FftField> Mul for DensePolynomial<F> {
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self {
+        &self * &rhs
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::transcript::Transcript;
 
     use bls_12_381::Fr as Scalar;
     use zkstd::common::{Group, PrimeField};
 
-    fn poly_mul<F: FftField>(a: &Polynomial<F>, b: &Polynomial<F>) -> Polynomial<F> {
-        let size = a.coeffs.len() + b.coeffs.len() - 1;
-        let mut coeffs = (0..size).map(|_| F::zero()).collect::<Vec<_>>();
-        for (i, ac) in a.coeffs.iter().enumerate() {
-            for (j, bc) in b.coeffs.iter().enumerate() {
-                coeffs[i + j] += *ac * *bc;
-            }
-        }
-        Polynomial { coeffs }
-    }
-
     #[test]
     fn inner_product_proof_test() {
         // setup
         let k = 8;
-        let x = Scalar::random(OsRng);
-        let x_inv = x.invert().unwrap();
-        let xx = x.square();
-        let xx_inv = x_inv.square();
-        let a_poly = Polynomial::<Scalar>::random(k);
-        let b_poly = Polynomial::<Scalar>::random(k);
+        let mut transcript = Transcript::new(b"inner-product");
+        let a_poly = DensePolynomial::<Scalar>::random(k);
+        let b_poly = DensePolynomial::<Scalar>::random(k);
 
         // compress
         let (alo, ahi) = a_poly.half();
@@ -115,16 +340,22 @@ mod tests {
         let c = alo.inner_product(&blo) + ahi.inner_product(&bhi);
         let l = alo.inner_product(&bhi);
         let r = ahi.inner_product(&blo);
+        transcript.append_scalar(b"l", &l);
+        transcript.append_scalar(b"r", &r);
+        let x: Scalar = transcript.challenge_scalar(b"x");
+        let x_inv = x.invert().unwrap();
+        let xx = x.square();
+        let xx_inv = x_inv.square();
         let xx_l = xx * l;
         let xx_inv_r = xx_inv * r;
-        let c_prime = c + xx_l + xx_inv_r;
+        let _c_prime = c + xx_l + xx_inv_r;
     }
 
     #[test]
     fn inner_product_test() {
         let k = 8;
-        let a_poly = Polynomial::<Scalar>::random(k);
-        let b_poly = Polynomial::<Scalar>::random(k);
+        let a_poly = DensePolynomial::<Scalar>::random(k);
+        let b_poly = DensePolynomial::<Scalar>::random(k);
         let naive_product = a_poly.inner_product(&b_poly);
 
         let (alo, ahi) = a_poly.half();
@@ -142,15 +373,166 @@ mod tests {
             .map(|_| Scalar::random(OsRng))
             .collect::<Vec<_>>();
         let factor_coeffs = vec![-r, Scalar::one()];
-        let a = Polynomial { coeffs };
-        let b = Polynomial {
+        let a = DensePolynomial { coeffs };
+        let b = DensePolynomial {
             coeffs: factor_coeffs,
         };
         // a * b
-        let c = poly_mul(&a, &b);
+        let c = &a * &b;
         // a * b / b
         let d = c.divide(&r);
 
         assert_eq!(a, d);
     }
+
+    #[test]
+    fn polynomial_ops_test() {
+        let a = DensePolynomial::<Scalar>::random(3);
+        let b = DensePolynomial::<Scalar>::random(5);
+        let at = Scalar::random(OsRng);
+        let (a_eval, b_eval) = (a.evaluate(at), b.evaluate(at));
+
+        assert_eq!((&a + &b).evaluate(at), a_eval + b_eval);
+        assert_eq!((&a - &b).evaluate(at), a_eval - b_eval);
+        assert_eq!((-&b).evaluate(at), -b_eval);
+        assert_eq!((&a * at).evaluate(at), a_eval * at);
+        // cancelled leading terms are trimmed
+        assert!((&b - &b).coeffs.is_empty());
+        assert_eq!((&a + &b) - b.clone(), a);
+        assert!((&a * Scalar::zero()).coeffs.is_empty());
+
+        // schoolbook and fft agree across threshold
+        let c = DensePolynomial::<Scalar>::random(6);
+        let schoolbook = &a * &c;
+        let fft = &b * &c;
+        assert_eq!(schoolbook.coeffs.len(), 8 + 64 - 1);
+        assert_eq!(fft.coeffs.len(), 32 + 64 - 1);
+        assert_eq!(schoolbook.evaluate(at), a_eval * c.evaluate(at));
+        assert_eq!(fft.evaluate(at), b_eval * c.evaluate(at));
+        assert_eq!(&(&a * &b) * &c, &a * &fft);
+        assert!((&a * &DensePolynomial::new(Vec::new())).coeffs.is_empty());
+
+        let mut acc = a.clone();
+        acc += &b;
+        assert_eq!(acc, a + b);
+    }
+
+    #[test]
+    fn divide_by_vanishing_test() {
+        let n = 8;
+        let mut vanishing = vec![Scalar::zero(); n + 1];
+        vanishing[0] = -Scalar::one();
+        vanishing[n] = Scalar::one();
+        let vanishing = DensePolynomial::new(vanishing);
+
+        for k in [2, 3, 5] {
+            let a = DensePolynomial::<Scalar>::random(k);
+            let (q, r) = a.divide_by_vanishing(n);
+            assert!(r.coeffs.len() <= n);
+            assert_eq!(&(&q * &vanishing) + &r, a);
+            assert_eq!((q, r), a.div_rem(&vanishing));
+        }
+
+        // multiple of x^n - 1 leaves no remainder
+        let b = DensePolynomial::<Scalar>::random(4);
+        let (q, r) = (&b * &vanishing).divide_by_vanishing(n);
+        assert_eq!(q, b);
+        assert!(r.coeffs.is_empty());
+    }
+
+    #[test]
+    fn div_rem_test() {
+        let a = DensePolynomial::<Scalar>::random(5);
+        for k in [1, 2, 4, 5] {
+            let mut b = DensePolynomial::<Scalar>::random(k);
+            b.coeffs.pop();
+            let (q, r) = a.div_rem(&b);
+            assert!(r.coeffs.len() < b.coeffs.len());
+            assert_eq!(&q * &b + r, a);
+        }
+
+        // divisor of higher degree and divisor with leading zeros
+        let b = DensePolynomial::<Scalar>::random(6);
+        assert_eq!(a.div_rem(&b), (DensePolynomial::new(Vec::new()), a.clone()));
+        let r = Scalar::random(OsRng);
+        let linear = DensePolynomial::new(vec![-r, Scalar::one(), Scalar::zero()]);
+        assert_eq!(a.div_rem(&linear).0, a.divide(&r));
+
+        // newton iteration for long quotient and divisor
+        let a = DensePolynomial::<Scalar>::random(8);
+        let mut b = DensePolynomial::<Scalar>::random(7);
+        b.coeffs.pop();
+        let (q, r) = a.div_rem(&b);
+        assert_eq!(q.coeffs.len(), 130);
+        assert!(r.coeffs.len() < b.coeffs.len());
+        assert_eq!(&q * &b + r, a);
+    }
+
+    fn naive_interpolate(points: &[(Scalar, Scalar)]) -> DensePolynomial<Scalar> {
+        points
+            .iter()
+            .enumerate()
+            .fold(DensePolynomial::new(Vec::new()), |acc, (i, (x_i, y_i))| {
+                let basis = points.iter().enumerate().filter(|(j, _)| *j != i).fold(
+                    DensePolynomial::new(vec![*y_i]),
+                    |basis, (_, (x_j, _))| {
+                        let inv = (*x_i - x_j).invert().unwrap();
+                        &basis * &DensePolynomial::new(vec![-*x_j * inv, inv])
+                    },
+                );
+                acc + basis
+            })
+    }
+
+    #[test]
+    fn evaluate_many_test() {
+        let points = (0..100).map(|_| Scalar::random(OsRng)).collect::<Vec<_>>();
+        for k in [0, 3, 7, 9] {
+            let a = DensePolynomial::<Scalar>::random(k);
+            let evals = a.evaluate_many(&points);
+            assert_eq!(
+                evals,
+                points.iter().map(|x| a.evaluate(*x)).collect::<Vec<_>>()
+            );
+        }
+        let a = DensePolynomial::<Scalar>::random(3);
+        assert!(a.evaluate_many(&[]).is_empty());
+        assert_eq!(
+            DensePolynomial::new(Vec::new()).evaluate_many(&points[..3]),
+            vec![Scalar::zero(); 3]
+        );
+    }
+
+    #[test]
+    fn interpolate_test() {
+        for n in [1, 2, 7, 70] {
+            let points = (0..n)
+                .map(|_| (Scalar::random(OsRng), Scalar::random(OsRng)))
+                .collect::<Vec<_>>();
+            let poly = DensePolynomial::interpolate(&points);
+            assert!(poly.coeffs.len() <= n);
+            assert!(points.iter().all(|(x, y)| poly.evaluate(*x) == *y));
+            assert_eq!(poly, naive_interpolate(&points));
+        }
+
+        // polynomial is recovered from as many points as coefficients
+        let a = DensePolynomial::<Scalar>::random(6);
+        let xs = (0..64).map(|i| Scalar::from(i as u64)).collect::<Vec<_>>();
+        let points = xs
+            .iter()
+            .copied()
+            .zip(a.evaluate_many(&xs))
+            .collect::<Vec<_>>();
+        assert_eq!(DensePolynomial::interpolate(&points), a);
+        assert!(DensePolynomial::<Scalar>::interpolate(&[])
+            .coeffs
+            .is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "interpolation points must be distinct")]
+    fn interpolate_duplicate_test() {
+        let (x, y) = (Scalar::random(OsRng), Scalar::random(OsRng));
+        DensePolynomial::interpolate(&[(x, y), (Scalar::one(), y), (x, y)]);
+    }
 }
@@ -0,0 +1,75 @@
+//! subproduct tree of x - x_i for multi point evaluation and interpolation
+use zkstd::common::FftField;
+
+use super::DensePolynomial;
+
+// levels from leaves x - x_i to root ∏(x - x_i)
+// j th node is product of 2j th and 2j + 1 th nodes of level below
+pub(crate) struct SubproductTree<F: FftField> {
+    levels: Vec<Vec<DensePolynomial<F>>>,
+}
+
+impl<F: FftField> SubproductTree<F> {
+    pub(crate) fn new(points: &[F]) -> Self {
+        assert!(!points.is_empty());
+        let leaves = points
+            .iter()
+            .map(|x| DensePolynomial::new(vec![-*x, F::one()]))
+            .collect::<Vec<_>>();
+        let mut levels = vec![leaves];
+        while levels[levels.len() - 1].len() > 1 {
+            let parents = levels[levels.len() - 1]
+                .chunks(2)
+                .map(|nodes| match nodes {
+                    [left, right] => left * right,
+                    [node] => node.clone(),
+                    _ => unreachable!(),
+                })
+                .collect();
+            levels.push(parents);
+        }
+        Self { levels }
+    }
+
+    /// m(x) = ∏(x - x_i)
+    pub(crate) fn root(&self) -> &DensePolynomial<F> {
+        &self.levels[self.levels.len() - 1][0]
+    }
+
+    /// p(x_i) = p mod (x - x_i) by reducing remainders from root to leaves
+    pub(crate) fn evaluate(&self, poly: &DensePolynomial<F>) -> Vec<F> {
+        let mut remainders = vec![poly.div_rem(self.root()).1];
+        for level in self.levels.iter().rev().skip(1) {
+            remainders = level
+                .iter()
+                .enumerate()
+                .map(|(j, node)| remainders[j / 2].div_rem(node).1)
+                .collect();
+        }
+        remainders
+            .iter()
+            .map(|remainder| remainder.coeffs.first().copied().unwrap_or(F::zero()))
+            .collect()
+    }
+
+    /// Σ c_i * m(x) / (x - x_i) by combining from leaves to root
+    pub(crate) fn linear_combination(&self, weights: &[F]) -> DensePolynomial<F> {
+        assert_eq!(weights.len(), self.levels[0].len());
+        let mut combinations = weights
+            .iter()
+            .map(|weight| DensePolynomial::new(vec![*weight]))
+            .collect::<Vec<_>>();
+        for level in self.levels.iter().take(self.levels.len() - 1) {
+            combinations = combinations
+                .chunks(2)
+                .zip(level.chunks(2))
+                .map(|pair| match pair {
+                    ([c_left, c_right], [left, right]) => &(c_left * right) + &(c_right * left),
+                    ([c], [_]) => c.clone(),
+                    _ => unreachable!(),
+                })
+                .collect();
+        }
+        combinations.pop().unwrap()
+    }
+}
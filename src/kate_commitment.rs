@@ -1,77 +1,269 @@
-use crate::inner_product::Polynomial;
+use crate::{
+    evaluations::Evaluations,
+    fft::Fft,
+    inner_product::DensePolynomial,
+    msm::{batch_normalize, fixed_base_mul, msm},
+    utils::powers_of,
+};
 
-use zkstd::behave::{CurveGroup, FftField, Pairing};
+mod multiopen;
+mod srs;
 
-pub(crate) struct Proof<P: Pairing> {
-    a: P::G1Affine,
-    b: P::G2Affine,
-    c: P::G1Affine,
+pub(crate) use multiopen::{GwcProof, ProverQuery, VerifierQuery};
+use zkstd::behave::{CurveAffine, CurveGroup, FftField, Group, Pairing, Ring};
+
+/// powers of secret in G1 for commitment and opening
+#[derive(Clone, Debug)]
+pub(crate) struct ProverKey<P: Pairing> {
+    g: Vec<P::G1Affine>,
+    // L_0(r) * G1, L_1(r) * G1, ..., L_n-1(r) * G1 over precomputed domain
+    lagrange_g: Vec<P::G1Affine>,
 }
 
-impl<P: Pairing> Proof<P> {
-    pub(crate) fn new(a: P::G1Affine, b: P::G2Affine, c: P::G1Affine) -> Self {
-        Self { a, b, c }
+impl<P: Pairing> ProverKey<P> {
+    /// c_0 + c_1 * x + c_2 * x^2 + ... + c_d * x^d
+    pub(crate) fn commit(&self, polynomial: &DensePolynomial<P::ScalarField>) -> P::G1Affine {
+        assert!(
+            polynomial.coeffs.len() <= self.g.len(),
+            "polynomial degree {} exceeds max degree {}",
+            polynomial.coeffs.len() - 1,
+            self.max_degree()
+        );
+        msm(&self.g, &polynomial.coeffs).into()
+    }
+
+    /// highest degree polynomial that can be committed
+    pub(crate) fn max_degree(&self) -> usize {
+        self.g.len() - 1
+    }
+
+    /// size of precomputed lagrange basis domain
+    pub(crate) fn lagrange_size(&self) -> usize {
+        self.lagrange_g.len()
+    }
+
+    /// L_0(r) * G1, L_1(r) * G1, ..., L_n-1(r) * G1 over precomputed domain
+    pub(crate) fn lagrange_basis(&self) -> &[P::G1Affine] {
+        &self.lagrange_g
+    }
+
+    /// e_0 * L_0(x) + e_1 * L_1(x) + ... + e_n-1 * L_n-1(x) without interpolation
+    pub(crate) fn commit_lagrange(&self, evals: &Evaluations<P::ScalarField>) -> P::G1Affine {
+        assert_eq!(
+            evals.size(),
+            self.lagrange_g.len(),
+            "lagrange basis is not precomputed for {} size domain",
+            evals.size()
+        );
+        assert!(
+            evals.offset() == P::ScalarField::one(),
+            "evaluations over coset"
+        );
+        msm(&self.lagrange_g, evals.evals()).into()
+    }
+
+    /// lagrange basis over fft domain by inverse fft of r^i * G1
+    pub(crate) fn precompute_lagrange(&mut self, fft: &Fft<P::ScalarField>) {
+        let n = fft.size();
+        assert!(self.g.len() >= n);
+        let mut points = self.g[..n]
+            .iter()
+            .map(|g| g.to_extended())
+            .collect::<Vec<_>>();
+        fft.idft_points(&mut points);
+        self.lagrange_g = batch_normalize(&points);
+    }
+
+    /// evaluate at a and commit (f(x) - f(a)) / (x - a)
+    pub(crate) fn open(
+        &self,
+        polynomial: &DensePolynomial<P::ScalarField>,
+        at: P::ScalarField,
+    ) -> (P::ScalarField, OpeningProof<P>) {
+        let eval = polynomial.evaluate(at);
+        let witness = self.commit(&polynomial.divide(&at));
+        (eval, OpeningProof { witness })
+    }
+
+    /// open polynomials at a with single proof combined by powers of v
+    pub(crate) fn open_batch(
+        &self,
+        polynomials: &[&DensePolynomial<P::ScalarField>],
+        at: P::ScalarField,
+        v: P::ScalarField,
+    ) -> (Vec<P::ScalarField>, OpeningProof<P>) {
+        let evals = polynomials
+            .iter()
+            .map(|polynomial| polynomial.evaluate(at))
+            .collect();
+        let (_, witness) = self.open(&linear_combination(polynomials, v), at);
+        (evals, witness)
     }
+}
+
+/// generators and secret in G2 for opening verification
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct VerifierKey<P: Pairing> {
+    g: P::G1Affine,
+    h: P::G2Affine,
+    r_h: P::G2Affine,
+}
 
-    pub(crate) fn verify(self) -> bool {
-        let Proof { a, b, c } = self;
-        let lhs = P::pairing(a, b);
-        let rhs = P::pairing(c, P::G2Affine::ADDITIVE_GENERATOR);
+impl<P: Pairing> VerifierKey<P> {
+    /// e(w, r * H - a * H) = e(c - f(a) * G, H)
+    pub(crate) fn verify_opening(
+        &self,
+        commitment: P::G1Affine,
+        at: P::ScalarField,
+        eval: P::ScalarField,
+        proof: &OpeningProof<P>,
+    ) -> bool {
+        let lhs = P::pairing(proof.witness, (self.r_h - self.h * at).into());
+        let rhs = P::pairing((commitment - self.g * eval).into(), self.h);
         lhs == rhs
     }
+
+    /// verify batch opening with single pairing check
+    pub(crate) fn verify_batch(
+        &self,
+        commitments: &[P::G1Affine],
+        at: P::ScalarField,
+        evals: &[P::ScalarField],
+        v: P::ScalarField,
+        proof: &OpeningProof<P>,
+    ) -> bool {
+        assert_eq!(commitments.len(), evals.len());
+        // c_0 + v * c_1 + v^2 * c_2 + ...
+        let (commitment, eval) = commitments.iter().zip(evals.iter()).rev().fold(
+            (P::G1Projective::ADDITIVE_IDENTITY, P::ScalarField::zero()),
+            |(commitment, eval), (c, e)| (commitment * v + *c, eval * v + *e),
+        );
+        self.verify_opening(commitment.into(), at, eval, proof)
+    }
+}
+
+/// commitment to quotient polynomial (f(x) - f(a)) / (x - a)
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct OpeningProof<P: Pairing> {
+    pub(crate) witness: P::G1Affine,
 }
 
 pub(crate) struct KateCommitment<P: Pairing> {
-    g: Vec<P::G1Affine>,
-    h: P::G2Affine,
+    pk: ProverKey<P>,
+    vk: VerifierKey<P>,
 }
 
 impl<P: Pairing> KateCommitment<P> {
+    /// setup from known trapdoor r which is only acceptable in tests
     pub(crate) fn new(k: usize, r: P::ScalarField) -> Self {
-        // G1, r * G1, r^2 * G1, ..., r^n-1 * G1
-        let g = (0..=1 << k)
-            .map(|i| {
-                let tw = P::G1Projective::ADDITIVE_GENERATOR * r.pow(i);
-                P::G1Affine::from(tw)
-            })
-            .collect::<Vec<_>>();
-        let h = (P::G2Affine::ADDITIVE_GENERATOR * r).into();
-        Self { g, h }
+        // G1, r * G1, r^2 * G1, ..., r^n * G1
+        let powers = powers_of(r, (1 << k) + 1);
+        let g = fixed_base_mul(P::G1Affine::ADDITIVE_GENERATOR, &powers);
+        let vk = VerifierKey {
+            g: P::G1Affine::ADDITIVE_GENERATOR,
+            h: P::G2Affine::ADDITIVE_GENERATOR,
+            r_h: (P::G2Affine::ADDITIVE_GENERATOR * r).into(),
+        };
+        Self {
+            pk: ProverKey {
+                g,
+                lagrange_g: Vec::new(),
+            },
+            vk,
+        }
+    }
+
+    /// precompute lagrange basis of fft domain for commit_lagrange
+    pub(crate) fn precompute_lagrange(&mut self, fft: &Fft<P::ScalarField>) {
+        self.pk.precompute_lagrange(fft)
     }
 
     /// c_0 + c_1 * x + c_2 * x^2 + ... + c_d * x^d
-    pub(crate) fn commit(&self, polynomial: &Polynomial<P::ScalarField>) -> P::G1Affine {
-        polynomial
-            .coeffs
-            .iter()
-            .zip(self.g.iter())
-            .fold(P::G1Projective::ADDITIVE_IDENTITY, |sum, (scalar, base)| {
-                sum + *base * *scalar
-            })
-            .into()
+    pub(crate) fn commit(&self, polynomial: &DensePolynomial<P::ScalarField>) -> P::G1Affine {
+        self.pk.commit(polynomial)
+    }
+
+    pub(crate) fn commit_lagrange(&self, evals: &Evaluations<P::ScalarField>) -> P::G1Affine {
+        self.pk.commit_lagrange(evals)
+    }
+
+    pub(crate) fn open(
+        &self,
+        polynomial: &DensePolynomial<P::ScalarField>,
+        at: P::ScalarField,
+    ) -> (P::ScalarField, OpeningProof<P>) {
+        self.pk.open(polynomial, at)
+    }
+
+    pub(crate) fn verify_opening(
+        &self,
+        commitment: P::G1Affine,
+        at: P::ScalarField,
+        eval: P::ScalarField,
+        proof: &OpeningProof<P>,
+    ) -> bool {
+        self.vk.verify_opening(commitment, at, eval, proof)
     }
 
-    pub(crate) fn get_h(&self) -> P::G2Affine {
-        self.h
+    pub(crate) fn open_batch(
+        &self,
+        polynomials: &[&DensePolynomial<P::ScalarField>],
+        at: P::ScalarField,
+        v: P::ScalarField,
+    ) -> (Vec<P::ScalarField>, OpeningProof<P>) {
+        self.pk.open_batch(polynomials, at, v)
     }
+
+    pub(crate) fn verify_batch(
+        &self,
+        commitments: &[P::G1Affine],
+        at: P::ScalarField,
+        evals: &[P::ScalarField],
+        v: P::ScalarField,
+        proof: &OpeningProof<P>,
+    ) -> bool {
+        self.vk.verify_batch(commitments, at, evals, v, proof)
+    }
+
+    pub(crate) fn prover_key(&self) -> &ProverKey<P> {
+        &self.pk
+    }
+
+    pub(crate) fn verifier_key(&self) -> VerifierKey<P> {
+        self.vk
+    }
+}
+
+// p_0 + v * p_1 + v^2 * p_2 + ...
+fn linear_combination<F: FftField>(
+    polynomials: &[&DensePolynomial<F>],
+    v: F,
+) -> DensePolynomial<F> {
+    polynomials
+        .iter()
+        .rev()
+        .fold(DensePolynomial::new(Vec::new()), |acc, polynomial| {
+            acc * v + (*polynomial).clone()
+        })
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{KateCommitment, Polynomial, Proof};
+    use super::{DensePolynomial, Evaluations, KateCommitment};
+    use crate::fft::Fft;
 
-    use bls_12_381::{Fr as Scalar, G1Affine as G1, G2Affine as G2};
+    use bls_12_381::{Fr as Scalar, G1Affine as G1};
     use ec_pairing::TatePairing;
     use rand::rngs::OsRng;
     use zkstd::behave::{CurveAffine, CurveGroup, Group, Pairing};
 
     fn sample_data<P: Pairing>(
         r: P::ScalarField,
-    ) -> (Polynomial<P::ScalarField>, KateCommitment<P>) {
+    ) -> (DensePolynomial<P::ScalarField>, KateCommitment<P>) {
         let k = 8;
         let pp = KateCommitment::<P>::new(k, r);
         let coeffs = (0..1 << k).map(|_| P::ScalarField::random(OsRng)).collect();
-        let poly = Polynomial::new(coeffs);
+        let poly = DensePolynomial::new(coeffs);
         (poly, pp)
     }
 
@@ -85,37 +277,73 @@ mod tests {
         assert_eq!(commitment.to_extended(), eval * G1::ADDITIVE_GENERATOR)
     }
 
+    #[test]
+    #[should_panic(expected = "polynomial degree 5 exceeds max degree 4")]
+    fn commit_degree_test() {
+        let pp = KateCommitment::<TatePairing>::new(2, Scalar::random(OsRng));
+        pp.commit(&DensePolynomial::new(vec![Scalar::one(); 6]));
+    }
+
+    #[test]
+    fn commit_lagrange_test() {
+        let r = Scalar::random(OsRng);
+        let mut pp = KateCommitment::<TatePairing>::new(6, r);
+        let fft = Fft::new(5);
+        pp.precompute_lagrange(&fft);
+        let evals = (0..32).map(|_| Scalar::random(OsRng)).collect::<Vec<_>>();
+        let evals = Evaluations::new(evals, 32);
+        let poly = fft.idft(evals.clone());
+        assert_eq!(pp.commit_lagrange(&evals), pp.commit(&poly));
+
+        // shorter evaluations are padded with zero
+        let evals = Evaluations::new(evals.evals()[..20].to_vec(), 32);
+        let poly = fft.idft(evals.clone());
+        assert_eq!(pp.commit_lagrange(&evals), pp.commit(&poly));
+    }
+
     #[test]
     fn kzg_test() {
         // setup params
         let r = Scalar::random(OsRng);
         let (poly, pp) = sample_data::<TatePairing>(r);
+        let commitment = pp.commit(&poly);
+        let vk = pp.verifier_key();
 
         // verifier
         let b = Scalar::random(OsRng);
 
         // prover
-        // 1. params computation
-
-        // f(b): evaluate at b
-        let b_eval = poly.evaluate(b);
-        // q(x): compute quotient f(x) - f(b) / x - b
-        let q_poly = poly.divide(&b);
-        let b_g2 = G2::ADDITIVE_GENERATOR * b;
-        let h = pp.get_h();
+        let (b_eval, proof) = pp.open(&poly, b);
+        assert_eq!(b_eval, poly.evaluate(b));
 
-        // 2. generate proof
+        // proof verification
+        assert!(pp.verify_opening(commitment, b, b_eval, &proof));
+        assert!(vk.verify_opening(commitment, b, b_eval, &proof));
+        assert!(!vk.verify_opening(commitment, b, b_eval + Scalar::one(), &proof));
+        assert!(!vk.verify_opening(commitment, b + Scalar::one(), b_eval, &proof));
+    }
 
-        // a - b
-        let a = pp.commit(&q_poly);
-        // commit q(a)
-        let b = (h - b_g2).into();
-        // f(a) - f(b)
-        let c = (pp.commit(&poly) - G1::ADDITIVE_GENERATOR * b_eval).into();
+    #[test]
+    fn kzg_batch_test() {
+        let r = Scalar::random(OsRng);
+        let pp = KateCommitment::<TatePairing>::new(8, r);
+        let polys = (0..6)
+            .map(|i| DensePolynomial::<Scalar>::random(8 - i))
+            .collect::<Vec<_>>();
+        let polys = polys.iter().collect::<Vec<_>>();
+        let commitments = polys.iter().map(|poly| pp.commit(poly)).collect::<Vec<_>>();
+        let (at, v) = (Scalar::random(OsRng), Scalar::random(OsRng));
 
-        let proof: Proof<TatePairing> = Proof::new(a, b, c);
+        let (evals, proof) = pp.open_batch(&polys, at, v);
+        assert!(polys
+            .iter()
+            .zip(evals.iter())
+            .all(|(poly, eval)| poly.evaluate(at) == *eval));
+        assert!(pp.verify_batch(&commitments, at, &evals, v, &proof));
 
-        // 3. proof verification
-        assert!(proof.verify())
+        let mut invalid_evals = evals.clone();
+        invalid_evals[3] += Scalar::one();
+        assert!(!pp.verify_batch(&commitments, at, &invalid_evals, v, &proof));
+        assert!(!pp.verify_batch(&commitments, at, &evals, v + Scalar::one(), &proof));
     }
 }
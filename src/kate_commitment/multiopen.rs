@@ -0,0 +1,291 @@
+//! multi-point openings
+//! GWC19 https://eprint.iacr.org/2019/953.pdf#page=13
+//! SHPLONK https://eprint.iacr.org/2020/081.pdf#page=11
+use zkstd::behave::{CurveGroup, FftField, Group, Pairing, Ring};
+
+use super::{linear_combination, ProverKey, VerifierKey};
+use crate::{inner_product::DensePolynomial, transcript::Transcript};
+
+/// polynomial opened at point
+#[derive(Clone, Debug)]
+pub(crate) struct ProverQuery<'a, F: FftField> {
+    pub(crate) poly: &'a DensePolynomial<F>,
+    pub(crate) point: F,
+}
+
+/// committed polynomial claimed to evaluate eval at point
+#[derive(Clone, Debug)]
+pub(crate) struct VerifierQuery<P: Pairing> {
+    pub(crate) commitment: P::G1Affine,
+    pub(crate) point: P::ScalarField,
+    pub(crate) eval: P::ScalarField,
+}
+
+/// one witness per distinct point
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct GwcProof<P: Pairing> {
+    pub(crate) witnesses: Vec<P::G1Affine>,
+}
+
+/// two witnesses for any number of points
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct ShplonkProof<P: Pairing> {
+    pub(crate) w: P::G1Affine,
+    pub(crate) w_prime: P::G1Affine,
+}
+
+impl<P: Pairing> ProverKey<P> {
+    /// combine polynomials at same point by powers of v and open each point
+    pub(crate) fn open_gwc(
+        &self,
+        queries: &[ProverQuery<P::ScalarField>],
+        transcript: &mut Transcript,
+    ) -> GwcProof<P> {
+        let v = transcript.challenge_scalar(b"v");
+        let witnesses = distinct_points(queries.iter().map(|query| query.point))
+            .into_iter()
+            .map(|point| {
+                let polys = queries
+                    .iter()
+                    .filter(|query| query.point == point)
+                    .map(|query| query.poly)
+                    .collect::<Vec<_>>();
+                let (_, proof) = self.open_batch(&polys, point, v);
+                transcript.append_point(b"w", &proof.witness);
+                proof.witness
+            })
+            .collect();
+        GwcProof { witnesses }
+    }
+
+    /// commit h(x) = Σ v^i Z_T\z_i(x)(f_i(x) - f_i(z_i)) / Z_T(x) and linearize at z
+    pub(crate) fn open_shplonk(
+        &self,
+        queries: &[ProverQuery<P::ScalarField>],
+        transcript: &mut Transcript,
+    ) -> ShplonkProof<P> {
+        let v = transcript.challenge_scalar(b"v");
+        let points = distinct_points(queries.iter().map(|query| query.point));
+        // f_i(x) - f_i(z_i)
+        let shifted = queries
+            .iter()
+            .map(|query| {
+                let eval = DensePolynomial::new(vec![query.poly.evaluate(query.point)]);
+                query.poly - &eval
+            })
+            .collect::<Vec<_>>();
+
+        let f = queries
+            .iter()
+            .zip(shifted.iter())
+            .map(|(query, poly)| {
+                points.iter().filter(|point| **point != query.point).fold(
+                    poly.clone(),
+                    |poly, point| {
+                        &poly * &DensePolynomial::new(vec![-*point, P::ScalarField::one()])
+                    },
+                )
+            })
+            .collect::<Vec<_>>();
+        let f = linear_combination(&f.iter().collect::<Vec<_>>(), v);
+        let h = points.iter().fold(f, |f, point| f.divide(point));
+        let w = self.commit(&h);
+        transcript.append_point(b"w", &w);
+        let z = transcript.challenge_scalar(b"z");
+
+        // L(x) = Σ v^i Z_T\z_i(z)(f_i(x) - f_i(z_i)) - Z_T(z) h(x)
+        let scaled = queries
+            .iter()
+            .zip(shifted)
+            .map(|(query, poly)| poly * vanishing_except(&points, query.point, z))
+            .collect::<Vec<_>>();
+        let l =
+            linear_combination(&scaled.iter().collect::<Vec<_>>(), v) - &h * vanishing(&points, z);
+        let w_prime = self.commit(&l.divide(&z));
+        transcript.append_point(b"w_prime", &w_prime);
+        ShplonkProof { w, w_prime }
+    }
+}
+
+impl<P: Pairing> VerifierKey<P> {
+    /// e(Σ u^i w_i, r * H) = e(Σ u^i (c_i - e_i * G + z_i * w_i), H)
+    pub(crate) fn verify_gwc(
+        &self,
+        queries: &[VerifierQuery<P>],
+        proof: &GwcProof<P>,
+        transcript: &mut Transcript,
+    ) -> bool {
+        let v = transcript.challenge_scalar(b"v");
+        proof
+            .witnesses
+            .iter()
+            .for_each(|witness| transcript.append_point(b"w", witness));
+        let u: P::ScalarField = transcript.challenge_scalar(b"u");
+
+        let points = distinct_points(queries.iter().map(|query| query.point));
+        if points.len() != proof.witnesses.len() {
+            return false;
+        }
+        let (lhs, rhs) = points.iter().zip(proof.witnesses.iter()).rev().fold(
+            (
+                P::G1Projective::ADDITIVE_IDENTITY,
+                P::G1Projective::ADDITIVE_IDENTITY,
+            ),
+            |(lhs, rhs), (point, witness)| {
+                let (commitment, eval) =
+                    combine::<P>(queries.iter().filter(|query| query.point == *point), v);
+                (
+                    lhs * u + *witness,
+                    rhs * u + commitment - self.g * eval + *witness * *point,
+                )
+            },
+        );
+        P::pairing(lhs.into(), self.r_h) == P::pairing(rhs.into(), self.h)
+    }
+
+    /// e(F + z * w', H) = e(w', r * H)
+    pub(crate) fn verify_shplonk(
+        &self,
+        queries: &[VerifierQuery<P>],
+        proof: &ShplonkProof<P>,
+        transcript: &mut Transcript,
+    ) -> bool {
+        let v: P::ScalarField = transcript.challenge_scalar(b"v");
+        transcript.append_point(b"w", &proof.w);
+        let z = transcript.challenge_scalar(b"z");
+        transcript.append_point(b"w_prime", &proof.w_prime);
+
+        let points = distinct_points(queries.iter().map(|query| query.point));
+        // F = Σ v^i Z_T\z_i(z)(c_i - e_i * G) - Z_T(z) * w
+        let f = queries
+            .iter()
+            .rev()
+            .fold(P::G1Projective::ADDITIVE_IDENTITY, |acc, query| {
+                let scalar = vanishing_except(&points, query.point, z);
+                acc * v + (query.commitment - self.g * query.eval) * scalar
+            });
+        let z_t = vanishing(&points, z);
+        let lhs = f - proof.w * z_t + proof.w_prime * z;
+        P::pairing(lhs.into(), self.h) == P::pairing(proof.w_prime, self.r_h)
+    }
+}
+
+// distinct points in order of first appearance
+fn distinct_points<F: FftField>(points: impl Iterator<Item = F>) -> Vec<F> {
+    points.fold(Vec::new(), |mut distinct, point| {
+        if !distinct.contains(&point) {
+            distinct.push(point)
+        }
+        distinct
+    })
+}
+
+// Σ v^i c_i and Σ v^i e_i
+fn combine<'a, P: Pairing + 'a>(
+    queries: impl DoubleEndedIterator<Item = &'a VerifierQuery<P>>,
+    v: P::ScalarField,
+) -> (P::G1Projective, P::ScalarField) {
+    queries.rev().fold(
+        (P::G1Projective::ADDITIVE_IDENTITY, P::ScalarField::zero()),
+        |(commitment, eval), query| (commitment * v + query.commitment, eval * v + query.eval),
+    )
+}
+
+// Π (z - z_j) over points
+fn vanishing<F: FftField>(points: &[F], z: F) -> F {
+    points.iter().fold(F::one(), |acc, point| acc * (z - point))
+}
+
+// Π (z - z_j) over points except skipped one
+fn vanishing_except<F: FftField>(points: &[F], skip: F, z: F) -> F {
+    points
+        .iter()
+        .filter(|point| **point != skip)
+        .fold(F::one(), |acc, point| acc * (z - point))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ProverQuery, VerifierQuery};
+    use crate::{
+        inner_product::DensePolynomial, kate_commitment::KateCommitment, transcript::Transcript,
+    };
+
+    use bls_12_381::Fr as Scalar;
+    use ec_pairing::TatePairing;
+    use rand::rngs::OsRng;
+    use zkstd::behave::Group;
+
+    // a at x, b at x and y, c at y and z
+    fn sample_queries(
+        pp: &KateCommitment<TatePairing>,
+        polys: &[DensePolynomial<Scalar>],
+    ) -> (Vec<(usize, Scalar)>, Vec<VerifierQuery<TatePairing>>) {
+        let (x, y, z) = (
+            Scalar::random(OsRng),
+            Scalar::random(OsRng),
+            Scalar::random(OsRng),
+        );
+        let indices = vec![(0, x), (1, x), (1, y), (2, y), (2, z)];
+        let queries = indices
+            .iter()
+            .map(|(i, point)| VerifierQuery {
+                commitment: pp.commit(&polys[*i]),
+                point: *point,
+                eval: polys[*i].evaluate(*point),
+            })
+            .collect();
+        (indices, queries)
+    }
+
+    #[test]
+    fn gwc_test() {
+        let r = Scalar::random(OsRng);
+        let pp = KateCommitment::<TatePairing>::new(6, r);
+        let polys = (4..7).map(DensePolynomial::random).collect::<Vec<_>>();
+        let (indices, queries) = sample_queries(&pp, &polys);
+        let prover_queries = indices
+            .iter()
+            .map(|(i, point)| ProverQuery {
+                poly: &polys[*i],
+                point: *point,
+            })
+            .collect::<Vec<_>>();
+
+        let proof = pp
+            .prover_key()
+            .open_gwc(&prover_queries, &mut Transcript::new(b"test"));
+        assert_eq!(proof.witnesses.len(), 3);
+        let vk = pp.verifier_key();
+        assert!(vk.verify_gwc(&queries, &proof, &mut Transcript::new(b"test")));
+
+        let mut invalid_queries = queries;
+        invalid_queries[2].eval += Scalar::one();
+        assert!(!vk.verify_gwc(&invalid_queries, &proof, &mut Transcript::new(b"test")));
+    }
+
+    #[test]
+    fn shplonk_test() {
+        let r = Scalar::random(OsRng);
+        let pp = KateCommitment::<TatePairing>::new(6, r);
+        let polys = (4..7).map(DensePolynomial::random).collect::<Vec<_>>();
+        let (indices, queries) = sample_queries(&pp, &polys);
+        let prover_queries = indices
+            .iter()
+            .map(|(i, point)| ProverQuery {
+                poly: &polys[*i],
+                point: *point,
+            })
+            .collect::<Vec<_>>();
+
+        let proof = pp
+            .prover_key()
+            .open_shplonk(&prover_queries, &mut Transcript::new(b"test"));
+        let vk = pp.verifier_key();
+        assert!(vk.verify_shplonk(&queries, &proof, &mut Transcript::new(b"test")));
+
+        let mut invalid_queries = queries;
+        invalid_queries[4].eval += Scalar::one();
+        assert!(!vk.verify_shplonk(&invalid_queries, &proof, &mut Transcript::new(b"test")));
+    }
+}
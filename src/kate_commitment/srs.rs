@@ -0,0 +1,304 @@
+//! structured reference string file
+//! | magic b"KZGSRS01" | n: u64 little endian | n G1 points | H: G2 | r * H: G2 |
+//! G1 points are G1, r * G1, ..., r^n-1 * G1 and every affine point is laid out as
+//! | x | y | infinity: u8 0 or 1 |
+//! where base field element is its montgomery form a * 2^384 mod q in six u64 little endian limbs
+//! and G2 coordinate c0 + c1 * u is c0 followed by c1 so that G1 point is 97 and G2 point 193 bytes
+//! this is the SCALE encoding of zkstd affine points, not the zcash nor ptau format
+use std::fmt;
+use std::io::{self, Read, Write};
+
+use rand::rngs::OsRng;
+use rayon::prelude::*;
+use zkstd::behave::{CurveAffine, CurveGroup, Group, Pairing, Ring};
+use zkstd::common::{Decode, Encode};
+
+use super::{KateCommitment, ProverKey, VerifierKey};
+use crate::{msm::msm, utils::powers_of};
+
+const MAGIC: &[u8; 8] = b"KZGSRS01";
+
+#[derive(Debug)]
+pub(crate) enum SrsError {
+    Io(io::Error),
+    InvalidFormat,
+    InvalidPoint(usize),
+    NotInSubgroup(usize),
+    InsufficientDegree { available: usize, required: usize },
+    InconsistentPowers,
+}
+
+impl fmt::Display for SrsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "srs io error: {err}"),
+            Self::InvalidFormat => write!(f, "srs is not in KZGSRS01 format"),
+            Self::InvalidPoint(i) => write!(f, "srs point {i} is not on curve"),
+            Self::NotInSubgroup(i) => write!(f, "srs point {i} is not in prime order subgroup"),
+            Self::InsufficientDegree {
+                available,
+                required,
+            } => write!(f, "srs has {available} powers but {required} are required"),
+            Self::InconsistentPowers => write!(f, "srs powers are not consecutive powers"),
+        }
+    }
+}
+
+impl From<io::Error> for SrsError {
+    fn from(err: io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+impl<P: Pairing> KateCommitment<P> {
+    /// read srs and truncate it to 2^k + 1 powers
+    /// unused powers are skipped without being decoded
+    pub(crate) fn read<R: Read>(reader: &mut R, k: usize) -> Result<Self, SrsError> {
+        if read_bytes(reader, 8)? != MAGIC {
+            return Err(SrsError::InvalidFormat);
+        }
+        let mut n = [0; 8];
+        n.copy_from_slice(&read_bytes(reader, 8)?);
+        let available =
+            usize::try_from(u64::from_le_bytes(n)).map_err(|_| SrsError::InvalidFormat)?;
+        let required = (1 << k) + 1;
+        if available < required {
+            return Err(SrsError::InsufficientDegree {
+                available,
+                required,
+            });
+        }
+
+        let g1_size = P::G1Affine::ADDITIVE_IDENTITY.encode().len();
+        let g2_size = P::G2Affine::ADDITIVE_IDENTITY.encode().len();
+        let mut g = Vec::with_capacity(required);
+        for i in 0..required {
+            let point: P::G1Affine = decode_point(reader, g1_size)?;
+            if !point.is_on_curve() {
+                return Err(SrsError::InvalidPoint(i));
+            }
+            g.push(point)
+        }
+        let skip = ((available - required) as u64)
+            .checked_mul(g1_size as u64)
+            .ok_or(SrsError::InvalidFormat)?;
+        if io::copy(&mut reader.take(skip), &mut io::sink())? != skip {
+            return Err(SrsError::InvalidFormat);
+        }
+        let h: P::G2Affine = decode_point(reader, g2_size)?;
+        let r_h: P::G2Affine = decode_point(reader, g2_size)?;
+        for (i, point) in [h, r_h].iter().enumerate() {
+            if !point.is_on_curve() {
+                return Err(SrsError::InvalidPoint(available + i));
+            }
+        }
+        if reader.read(&mut [0])? != 0 {
+            return Err(SrsError::InvalidFormat);
+        }
+
+        // on curve points may still have cofactor component
+        if let Some(i) = g.par_iter().position_first(|point| !in_subgroup(*point)) {
+            return Err(SrsError::NotInSubgroup(i));
+        }
+        if let Some(i) = [h, r_h].iter().position(|point| !in_subgroup(*point)) {
+            return Err(SrsError::NotInSubgroup(available + i));
+        }
+
+        let srs = Self {
+            vk: VerifierKey { g: g[0], h, r_h },
+            pk: ProverKey {
+                g,
+                lagrange_g: Vec::new(),
+            },
+        };
+        if srs.is_consistent() {
+            Ok(srs)
+        } else {
+            Err(SrsError::InconsistentPowers)
+        }
+    }
+
+    pub(crate) fn write<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        writer.write_all(MAGIC)?;
+        writer.write_all(&(self.pk.g.len() as u64).to_le_bytes())?;
+        for point in self.pk.g.iter() {
+            writer.write_all(&point.encode())?;
+        }
+        writer.write_all(&self.vk.h.encode())?;
+        writer.write_all(&self.vk.r_h.encode())
+    }
+
+    /// e(Σ ρ^i g_i+1, H) = e(Σ ρ^i g_i, r * H) for random ρ
+    fn is_consistent(&self) -> bool {
+        let g = &self.pk.g;
+        if g[0].is_identity() || self.vk.h.is_identity() {
+            return false;
+        }
+        let ρ = powers_of(P::ScalarField::random(OsRng), g.len() - 1);
+        let lhs = msm(&g[1..], &ρ).into();
+        let rhs = msm(&g[..g.len() - 1], &ρ).into();
+        P::pairing(lhs, self.vk.h) == P::pairing(rhs, self.vk.r_h)
+    }
+}
+
+// exactly len bytes where early end of input is malformed srs
+fn read_bytes<R: Read>(reader: &mut R, len: usize) -> Result<Vec<u8>, SrsError> {
+    let mut bytes = vec![0; len];
+    reader
+        .read_exact(&mut bytes)
+        .map_err(|err| match err.kind() {
+            io::ErrorKind::UnexpectedEof => SrsError::InvalidFormat,
+            _ => SrsError::Io(err),
+        })?;
+    Ok(bytes)
+}
+
+fn decode_point<C: Decode, R: Read>(reader: &mut R, size: usize) -> Result<C, SrsError> {
+    C::decode(&mut read_bytes(reader, size)?.as_slice()).map_err(|_| SrsError::InvalidFormat)
+}
+
+// r * P = (r - 1) * P + P vanishes only on prime order subgroup
+fn in_subgroup<C: CurveAffine>(point: C) -> bool {
+    let order_minus_one = -C::Scalar::one();
+    (point * order_minus_one + point).is_identity()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SrsError;
+    use crate::{inner_product::DensePolynomial, kate_commitment::KateCommitment};
+
+    use bls_12_381::{Fq, Fr as Scalar, G1Affine as G1, G2Affine as G2};
+    use ec_pairing::TatePairing;
+    use rand::rngs::OsRng;
+    use zkstd::behave::{CurveGroup, Group, PrimeField};
+    use zkstd::common::{Decode, Encode};
+
+    #[test]
+    fn srs_roundtrip_test() {
+        let r = Scalar::random(OsRng);
+        let pp = KateCommitment::<TatePairing>::new(6, r);
+        let mut bytes = Vec::new();
+        pp.write(&mut bytes).unwrap();
+
+        // truncate to lower degree
+        let loaded = KateCommitment::<TatePairing>::read(&mut bytes.as_slice(), 4).unwrap();
+        let poly = DensePolynomial::<Scalar>::random(4);
+        assert_eq!(loaded.commit(&poly), pp.commit(&poly));
+        let at = Scalar::random(OsRng);
+        let (eval, proof) = loaded.open(&poly, at);
+        assert!(pp.verify_opening(pp.commit(&poly), at, eval, &proof));
+
+        assert!(matches!(
+            KateCommitment::<TatePairing>::read(&mut bytes.as_slice(), 7),
+            Err(SrsError::InsufficientDegree {
+                available: 65,
+                required: 129
+            })
+        ));
+        assert!(matches!(
+            KateCommitment::<TatePairing>::read(&mut &bytes[..bytes.len() - 1], 4),
+            Err(SrsError::InvalidFormat)
+        ));
+        let mut trailing = bytes.clone();
+        trailing.push(0);
+        assert!(matches!(
+            KateCommitment::<TatePairing>::read(&mut trailing.as_slice(), 4),
+            Err(SrsError::InvalidFormat)
+        ));
+
+        // header claiming more powers than the file holds is not allocated
+        for n in [u64::MAX, 1 << 40] {
+            let mut oversized = bytes.clone();
+            oversized[8..16].copy_from_slice(&n.to_le_bytes());
+            assert!(matches!(
+                KateCommitment::<TatePairing>::read(&mut oversized.as_slice(), 4),
+                Err(SrsError::InvalidFormat)
+            ));
+        }
+    }
+
+    #[test]
+    fn srs_validation_test() {
+        let r = Scalar::random(OsRng);
+        let pp = KateCommitment::<TatePairing>::new(4, r);
+        let mut bytes = Vec::new();
+        pp.write(&mut bytes).unwrap();
+        let point_size = G1::ADDITIVE_GENERATOR.encode().len();
+        let offset = 16 + 3 * point_size;
+
+        // replace r^3 * G1 with another point on curve
+        let mut inconsistent = bytes.clone();
+        let point = G1::from(G1::ADDITIVE_GENERATOR * Scalar::random(OsRng));
+        inconsistent[offset..offset + point_size].copy_from_slice(&point.encode());
+        assert!(matches!(
+            KateCommitment::<TatePairing>::read(&mut inconsistent.as_slice(), 4),
+            Err(SrsError::InconsistentPowers)
+        ));
+
+        // corrupt y coordinate of r^3 * G1
+        let mut invalid = bytes;
+        invalid[offset + point_size / 2 + 1] ^= 1;
+        assert!(matches!(
+            KateCommitment::<TatePairing>::read(&mut invalid.as_slice(), 4),
+            Err(SrsError::InvalidPoint(3))
+        ));
+    }
+
+    #[test]
+    fn srs_layout_test() {
+        // 2^384 mod q is montgomery form of one
+        let r = [
+            0x760900000002fffd_u64,
+            0xebf4000bc40c0002,
+            0x5f48985753c758ba,
+            0x77ce585370525745,
+            0x5c071a97a256ec6d,
+            0x15f65ec3fa80e493,
+        ];
+        let one = r
+            .iter()
+            .flat_map(|limb| limb.to_le_bytes())
+            .collect::<Vec<_>>();
+        assert_eq!(Fq::one().encode(), one);
+        assert_eq!(G1::ADDITIVE_GENERATOR.encode().len(), 97);
+        assert_eq!(G2::ADDITIVE_GENERATOR.encode().len(), 193);
+        assert_eq!(G1::ADDITIVE_IDENTITY.encode()[96], 1);
+
+        let pp = KateCommitment::<TatePairing>::new(1, Scalar::random(OsRng));
+        let mut bytes = Vec::new();
+        pp.write(&mut bytes).unwrap();
+        assert_eq!(&bytes[..8], b"KZGSRS01");
+        assert_eq!(bytes[8..16], 3u64.to_le_bytes());
+        assert_eq!(bytes[16..16 + 97], G1::ADDITIVE_GENERATOR.encode());
+        assert_eq!(bytes.len(), 16 + 3 * 97 + 2 * 193);
+    }
+
+    #[test]
+    fn srs_subgroup_test() {
+        let r = Scalar::random(OsRng);
+        let pp = KateCommitment::<TatePairing>::new(2, r);
+        let mut bytes = Vec::new();
+        pp.write(&mut bytes).unwrap();
+
+        // y^2 = x^3 + 4 has points outside of r order subgroup
+        let point = (1..)
+            .find_map(|x| {
+                let x = Fq::from(x);
+                let y = (x.square() * x + Fq::from(4)).sqrt()?;
+                let mut encoded = x.encode();
+                encoded.extend(y.encode());
+                encoded.extend(false.encode());
+                let point = G1::decode(&mut encoded.as_slice()).unwrap();
+                (!point.is_torsion_free()).then_some(point)
+            })
+            .unwrap();
+        assert!(point.is_on_curve());
+        let offset = 16 + 2 * 97;
+        bytes[offset..offset + 97].copy_from_slice(&point.encode());
+        assert!(matches!(
+            KateCommitment::<TatePairing>::read(&mut bytes.as_slice(), 2),
+            Err(SrsError::NotInSubgroup(2))
+        ));
+    }
+}
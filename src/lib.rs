@@ -1,10 +1,15 @@
 #![allow(dead_code)]
+mod domain;
+mod evaluations;
 mod fft;
 mod inner_product;
 mod kate_commitment;
 mod lookup;
+mod msm;
 mod pedersen_commitment;
 mod range_proof;
-
-#[cfg(test)]
+mod transcript;
 mod utils;
+
+#[cfg(feature = "bench")]
+pub use fft::{Fft, FftStrategy};
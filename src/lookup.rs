@@ -1,94 +1,552 @@
 //! plookup protocol
 //! https://eprint.iacr.org/2020/315.pdf#page=6
+use std::collections::HashMap;
+
 use rand::rngs::OsRng;
-use zkstd::common::{FftField, PrimeField};
+use rand::RngCore;
+use zkstd::behave::{CurveGroup, Pairing};
+use zkstd::common::FftField;
 
+mod error;
+mod proof;
 mod table;
 
-use crate::{fft::Fft, inner_product::Polynomial};
-use table::XORTable;
+use crate::{
+    domain::{lagrange_basis_at, EvaluationDomain},
+    evaluations::Evaluations,
+    fft::{cube_root_of_unity, is_domain_size, root_of_unity, FftStrategy},
+    inner_product::DensePolynomial,
+    kate_commitment::{KateCommitment, ProverQuery, VerifierKey, VerifierQuery},
+    transcript::Transcript,
+};
+pub(crate) use error::LookupError;
+pub(crate) use proof::LookupProof;
+use proof::{Commitments, ProofEvaluations};
+use table::{compress_columns, LookupTable};
 
+/// witness rows looked up in table of same width
+#[derive(Clone, Debug)]
 pub(crate) struct Lookup<F: FftField> {
-    a: Vec<F>,
-    b: Vec<F>,
-    c: Vec<F>,
+    columns: Vec<Vec<F>>,
+    // butterflies for prover polynomials
+    strategy: FftStrategy,
 }
 
 impl<F: FftField> Lookup<F> {
-    pub(crate) fn new(a: Vec<F>, b: Vec<F>, c: Vec<F>) -> Self {
-        Self { a, b, c }
+    pub(crate) fn new(columns: Vec<Vec<F>>) -> Self {
+        assert!(!columns.is_empty());
+        assert!(columns
+            .iter()
+            .all(|column| column.len() == columns[0].len()));
+        Self {
+            columns,
+            strategy: FftStrategy::default(),
+        }
     }
 
-    pub(crate) fn prove(&self, alpha: F, table: XORTable<F>) {
-        let f = self.compress(alpha);
-        let t = table.compress(alpha);
-        let mut s = [f.clone(), t.clone()].concat();
-        s.sort();
-        let (h1, h2) = s.split_at(s.len() / 2);
-        let (β, y) = (F::random(OsRng), F::random(OsRng));
-        let z = compute_z(β, y, f, t, h1.to_vec(), h2.to_vec());
-
-        let n = t.len().next_power_of_two();
-        let k = n.trailing_zeros();
-        let fft: Fft<F> = Fft::new(k as usize);
-        let f_poly = Polynomial::from_evals(f, fft);
-        let t_poly = Polynomial::from_evals(t, fft);
-        let h1_poly = Polynomial::from_evals(h1.to_vec(), fft);
-        let h2_poly = Polynomial::from_evals(h2.to_vec(), fft);
-        let z_poly = Polynomial::from_evals(z, fft);
-        let q_poly = compute_q(f_poly, t_poly, h1_poly, h2_poly, z_poly, β, y);
-    }
-
-    fn compress(&self, alpha: F) -> Vec<F> {
-        let alpha2 = alpha.square();
-        self.a
+    /// prove with strategy instead of default fft
+    pub(crate) fn with_strategy(mut self, strategy: FftStrategy) -> Self {
+        self.strategy = strategy;
+        self
+    }
+
+    /// number of witness rows
+    pub(crate) fn len(&self) -> usize {
+        self.columns[0].len()
+    }
+
+    /// number of witness columns
+    pub(crate) fn width(&self) -> usize {
+        self.columns.len()
+    }
+
+    /// split width bit xors into bit_length limbs from least significant
+    /// each limb is looked up in bit_length xor table and recomposition is left to the circuit
+    pub(crate) fn decompose_xor(
+        a: &[u64],
+        b: &[u64],
+        width: usize,
+        bit_length: usize,
+    ) -> Result<Self, LookupError> {
+        if a.len() != b.len() {
+            return Err(LookupError::OperandCountMismatch {
+                a: a.len(),
+                b: b.len(),
+            });
+        }
+        if bit_length == 0 || bit_length >= 64 || width > 64 || !width.is_multiple_of(bit_length) {
+            return Err(LookupError::InvalidLimb { width, bit_length });
+        }
+        // higher bits would be dropped and another xor looked up
+        let fits = |x: u64| width == 64 || x >> width == 0;
+        if let Some(row) = a.iter().zip(b.iter()).position(|(x, y)| !fits(x | y)) {
+            return Err(LookupError::OperandTooWide { row, width });
+        }
+        let mask = (1 << bit_length) - 1;
+        let limbs = |x: u64| (0..width / bit_length).map(move |i| (x >> (i * bit_length)) & mask);
+        let (mut limb_a, mut limb_b, mut limb_c) = (Vec::new(), Vec::new(), Vec::new());
+        a.iter().zip(b.iter()).for_each(|(x, y)| {
+            limbs(*x).zip(limbs(*y)).for_each(|(x, y)| {
+                limb_a.push(F::from(x));
+                limb_b.push(F::from(y));
+                limb_c.push(F::from(x ^ y));
+            })
+        });
+        Ok(Self::new(vec![limb_a, limb_b, limb_c]))
+    }
+
+    /// columns are compressed with α drawn after witness and table columns are committed
+    pub(crate) fn prove<P: Pairing<ScalarField = F>>(
+        &self,
+        pp: &KateCommitment<P>,
+        table: &impl LookupTable<F>,
+    ) -> Result<LookupProof<P>, LookupError> {
+        self.prove_inner::<P, OsRng>(pp, table, None)
+    }
+
+    /// prove with f, h1, h2 and z blinded by random multiples of vanishing polynomial
+    pub(crate) fn prove_hiding<P: Pairing<ScalarField = F>>(
+        &self,
+        pp: &KateCommitment<P>,
+        table: &impl LookupTable<F>,
+        mut rng: impl RngCore,
+    ) -> Result<LookupProof<P>, LookupError> {
+        self.prove_inner(pp, table, Some(&mut rng))
+    }
+
+    fn prove_inner<P: Pairing<ScalarField = F>, R: RngCore>(
+        &self,
+        pp: &KateCommitment<P>,
+        table: &impl LookupTable<F>,
+        mut rng: Option<&mut R>,
+    ) -> Result<LookupProof<P>, LookupError> {
+        if self.width() != table.width() {
+            return Err(LookupError::WidthMismatch {
+                witness: self.width(),
+                table: table.width(),
+            });
+        }
+        if table.len() == 0 {
+            return Err(LookupError::EmptyTable);
+        }
+        let n = domain_size::<F>(self.len(), table.len())?;
+        // quotient degree is at most 2n - 2 and blinding raises it to 2n + 7
+        let required = if rng.is_some() { 2 * n + 7 } else { 2 * n - 2 };
+        let available = pp.prover_key().max_degree();
+        if available < required {
+            return Err(LookupError::InsufficientDegree {
+                available,
+                required,
+            });
+        }
+        let basis = pp.prover_key().lagrange_size();
+        if basis != n {
+            return Err(LookupError::LagrangeBasisMismatch { domain: n, basis });
+        }
+
+        // paper's |f| = n and |t| = n + 1 become |f| = n - 1 and |t| = n on n points
+        // every column is padded with its last table element so that padding commutes with compression
+        let f_columns = self
+            .columns
             .iter()
-            .zip(self.b.iter())
-            .zip(self.c.iter())
-            .map(|((t1, t2), t3)| *t1 + alpha * t2 + alpha2 * t3)
-            .collect()
+            .zip(table.columns())
+            .map(|(column, t_column)| {
+                let mut column = column.clone();
+                column.resize(n - 1, t_column[t_column.len() - 1]);
+                column
+            })
+            .collect::<Vec<_>>();
+
+        // f is opened at one point and the others at two so one more random coefficient each
+        let mut blind = |degree| match rng.as_mut() {
+            Some(rng) => blinding(n, degree, rng),
+            None => DensePolynomial::new(Vec::new()),
+        };
+        let f_blindings = f_columns.iter().map(|_| blind(1)).collect::<Vec<_>>();
+        let [h1_blinding, h2_blinding, z_blinding] = [2, 2, 2].map(&mut blind);
+        // commit evaluations directly with lagrange basis
+        let commit = |evals: &Evaluations<F>, blinding: &DensePolynomial<F>| -> P::G1Affine {
+            (pp.commit_lagrange(evals) + pp.commit(blinding)).into()
+        };
+        let f_commitments = f_columns
+            .iter()
+            .zip(f_blindings.iter())
+            .map(|(column, blinding)| commit(&Evaluations::new(column.clone(), n), blinding))
+            .collect::<Vec<_>>();
+
+        // α is drawn only after every column is bound
+        let mut transcript = Transcript::new(b"plookup");
+        table
+            .commit(pp, n)?
+            .iter()
+            .for_each(|commitment| transcript.append_point(b"t", commitment));
+        f_commitments
+            .iter()
+            .for_each(|commitment| transcript.append_point(b"f", commitment));
+        let alpha = transcript.challenge_scalar(b"alpha");
+
+        let t = table.preprocess(alpha, n)?;
+        let f = compress_columns(&f_columns, alpha);
+        let f_blinding = f_blindings
+            .iter()
+            .rev()
+            .fold(DensePolynomial::new(Vec::new()), |acc, blinding| {
+                &(acc * alpha) + blinding
+            });
+
+        let s = sort_by_table(&f, &t)?;
+        // h1 and h2 overlap at s_n
+        let (h1, h2) = (s[..n].to_vec(), s[n - 1..].to_vec());
+        let [f_evals, h1_evals, h2_evals] =
+            [&f, &h1, &h2].map(|evals| Evaluations::new(evals.clone(), n));
+        let (h1_commitment, h2_commitment) = (
+            commit(&h1_evals, &h1_blinding),
+            commit(&h2_evals, &h2_blinding),
+        );
+        transcript.append_point(b"h1", &h1_commitment);
+        transcript.append_point(b"h2", &h2_commitment);
+        let β = transcript.challenge_scalar(b"beta");
+        let y = transcript.challenge_scalar(b"gamma");
+
+        let z_evals = Evaluations::new(compute_z(β, y, &f, &t, &h1, &h2), n);
+        let z_commitment = commit(&z_evals, &z_blinding);
+        transcript.append_point(b"z", &z_commitment);
+        let δ: F = transcript.challenge_scalar(b"delta");
+
+        // coefficients are still needed for quotient and openings
+        let domain = EvaluationDomain::with_size(n, self.strategy);
+        let fft = domain.fft();
+        let [f_poly, h1_poly, h2_poly, z_poly] = [
+            (f_evals, f_blinding),
+            (h1_evals, h1_blinding),
+            (h2_evals, h2_blinding),
+            (z_evals, z_blinding),
+        ]
+        .map(|(evals, blinding)| fft.idft(evals) + blinding);
+        let t_poly = fft.idft(Evaluations::new(t, n));
+
+        let q_poly = compute_q(
+            &domain, &f_poly, &t_poly, &h1_poly, &h2_poly, &z_poly, β, y, δ,
+        );
+        let q_commitment = pp.commit(&q_poly);
+        transcript.append_point(b"q", &q_commitment);
+        let ζ = transcript.challenge_scalar(b"zeta");
+
+        let commitments = Commitments {
+            f: f_commitments,
+            h1: h1_commitment,
+            h2: h2_commitment,
+            z: z_commitment,
+            q: q_commitment,
+        };
+
+        // open at ζ and ωζ
+        let ωζ = domain.generator() * ζ;
+        let zeta_polys = [&f_poly, &t_poly, &h1_poly, &h2_poly, &z_poly, &q_poly];
+        let zeta_omega_polys = [&t_poly, &h1_poly, &h2_poly, &z_poly];
+        let evals = zeta_polys.map(|poly| poly.evaluate(ζ));
+        let omega_evals = zeta_omega_polys.map(|poly| poly.evaluate(ωζ));
+        let evaluations = ProofEvaluations {
+            f: evals[0],
+            t: evals[1],
+            t_omega: omega_evals[0],
+            h1: evals[2],
+            h1_omega: omega_evals[1],
+            h2: evals[3],
+            h2_omega: omega_evals[2],
+            z: evals[4],
+            z_omega: omega_evals[3],
+            q: evals[5],
+        };
+        evaluations
+            .zeta()
+            .iter()
+            .chain(evaluations.zeta_omega().iter())
+            .for_each(|eval| transcript.append_scalar(b"eval", eval));
+        let queries = zeta_polys
+            .iter()
+            .map(|poly| ProverQuery { poly, point: ζ })
+            .chain(
+                zeta_omega_polys
+                    .iter()
+                    .map(|poly| ProverQuery { poly, point: ωζ }),
+            )
+            .collect::<Vec<_>>();
+        let openings = pp.prover_key().open_gwc(&queries, &mut transcript);
+        Ok(LookupProof {
+            commitments,
+            evaluations,
+            openings,
+        })
+    }
+
+    /// table_commitments are the column commitments of LookupTable::commit
+    pub(crate) fn verify<P: Pairing<ScalarField = F>>(
+        vk: &VerifierKey<P>,
+        n: usize,
+        table_commitments: &[P::G1Affine],
+        proof: &LookupProof<P>,
+    ) -> bool {
+        let LookupProof {
+            commitments,
+            evaluations,
+            openings,
+        } = proof;
+        // sizes without a domain come from a malformed statement
+        if commitments.f.len() != table_commitments.len() || !is_domain_size::<F>(n) {
+            return false;
+        }
+
+        // recompute challenges
+        let mut transcript = Transcript::new(b"plookup");
+        table_commitments
+            .iter()
+            .for_each(|commitment| transcript.append_point(b"t", commitment));
+        commitments
+            .f
+            .iter()
+            .for_each(|commitment| transcript.append_point(b"f", commitment));
+        let alpha = transcript.challenge_scalar(b"alpha");
+        let f_commitment = compress_commitments::<P>(&commitments.f, alpha);
+        let table_commitment = compress_commitments::<P>(table_commitments, alpha);
+        transcript.append_point(b"h1", &commitments.h1);
+        transcript.append_point(b"h2", &commitments.h2);
+        let β = transcript.challenge_scalar(b"beta");
+        let y = transcript.challenge_scalar(b"gamma");
+        transcript.append_point(b"z", &commitments.z);
+        let δ: F = transcript.challenge_scalar(b"delta");
+        transcript.append_point(b"q", &commitments.q);
+        let ζ = transcript.challenge_scalar(b"zeta");
+
+        // verifier only needs ω so no fft is built
+        let ω = root_of_unity::<F>(n);
+        let ωζ = ω * ζ;
+
+        evaluations
+            .zeta()
+            .iter()
+            .chain(evaluations.zeta_omega().iter())
+            .for_each(|eval| transcript.append_scalar(b"eval", eval));
+
+        // check openings at ζ and ωζ
+        let zeta_commitments = [
+            f_commitment,
+            table_commitment,
+            commitments.h1,
+            commitments.h2,
+            commitments.z,
+            commitments.q,
+        ];
+        let zeta_omega_commitments = [
+            table_commitment,
+            commitments.h1,
+            commitments.h2,
+            commitments.z,
+        ];
+        let queries = zeta_commitments
+            .into_iter()
+            .zip(evaluations.zeta())
+            .map(|(commitment, eval)| VerifierQuery {
+                commitment,
+                point: ζ,
+                eval,
+            })
+            .chain(
+                zeta_omega_commitments
+                    .into_iter()
+                    .zip(evaluations.zeta_omega())
+                    .map(|(commitment, eval)| VerifierQuery {
+                        commitment,
+                        point: ωζ,
+                        eval,
+                    }),
+            )
+            .collect::<Vec<_>>();
+        let opened = vk.verify_gwc(&queries, openings, &mut transcript);
+
+        // check quotient identity at ζ
+        let one_β = F::one() + β;
+        let g_last = ω.pow(n as u64 - 1);
+        let vanishing = ζ.pow(n as u64) - F::one();
+        let l_first = lagrange_basis_at(n, F::one(), ζ);
+        let l_last = lagrange_basis_at(n, g_last, ζ);
+        let ProofEvaluations {
+            f,
+            t,
+            t_omega,
+            h1,
+            h1_omega,
+            h2,
+            h2_omega,
+            z,
+            z_omega,
+            q,
+        } = *evaluations;
+        let first = l_first * (z - F::one());
+        let grand_product = (ζ - g_last)
+            * (z * one_β * (y + f) * randomly_linear_combination(one_β, β, y, t, t_omega)
+                - z_omega
+                    * randomly_linear_combination(one_β, β, y, h1, h1_omega)
+                    * randomly_linear_combination(one_β, β, y, h2, h2_omega));
+        let overlap = l_last * (h1 - h2_omega);
+        let last = l_last * (z - F::one());
+        let numerator = first + δ * (grand_product + δ * (overlap + δ * last));
+
+        opened && numerator == q * vanishing
+    }
+}
+
+/// smallest 2^k or 3 * 2^k domain where |f| = n - 1 and |t| <= n
+pub(crate) fn domain_size<F: FftField>(f_len: usize, t_len: usize) -> Result<usize, LookupError> {
+    // blinded numerator degree 3n + 7 must fit in 4n extended domain
+    let n = t_len.max(f_len + 1).max(8);
+    // 3 * 2^k domains exist only if 3 divides p - 1
+    let mixed = cube_root_of_unity::<F>().map(|_| 3 * n.div_ceil(3).next_power_of_two());
+    // quotient is computed on 4n extended domain which must exist too
+    let max = if mixed.is_some() {
+        3 << (F::S - 2)
+    } else {
+        1 << (F::S - 2)
+    };
+    if n > max {
+        return Err(LookupError::DomainTooLarge { size: n, max });
+    }
+    Ok(mixed.map_or(n.next_power_of_two(), |mixed| {
+        mixed.min(n.next_power_of_two())
+    }))
+}
+
+// c_0 + α c_1 + α^2 c_2 + ... matching compress_columns
+fn compress_commitments<P: Pairing>(
+    commitments: &[P::G1Affine],
+    alpha: P::ScalarField,
+) -> P::G1Affine {
+    commitments
+        .iter()
+        .rev()
+        .fold(P::G1Projective::ADDITIVE_IDENTITY, |acc, commitment| {
+            acc * alpha + *commitment
+        })
+        .into()
+}
+
+// b(x)(x^n - 1) for random b of degree which vanishes on the whole domain
+fn blinding<F: FftField>(n: usize, degree: usize, rng: &mut impl RngCore) -> DensePolynomial<F> {
+    let mut coeffs = vec![F::zero(); n + degree + 1];
+    (0..=degree).for_each(|i| {
+        let b = F::random(&mut *rng);
+        coeffs[i] -= b;
+        coeffs[n + i] += b;
+    });
+    DensePolynomial::new(coeffs)
+}
+
+// f ∪ t where each f_i follows the first t_j equal to it
+fn sort_by_table<F: FftField>(f: &[F], t: &[F]) -> Result<Vec<F>, LookupError> {
+    let mut index = HashMap::with_capacity(t.len());
+    t.iter().enumerate().for_each(|(j, value)| {
+        index.entry(value.to_raw_bytes()).or_insert(j);
+    });
+    let mut counts = vec![0; t.len()];
+    for (row, value) in f.iter().enumerate() {
+        match index.get(&value.to_raw_bytes()) {
+            Some(j) => counts[*j] += 1,
+            None => return Err(LookupError::NotInTable { row }),
+        }
     }
+    let mut s = Vec::with_capacity(f.len() + t.len());
+    t.iter().zip(counts).for_each(|(value, count)| {
+        s.extend(vec![*value; count + 1]);
+    });
+    Ok(s)
 }
 
-fn compute_z<F: FftField>(β: F, y: F, f: Vec<F>, t: Vec<F>, h1: Vec<F>, h2: Vec<F>) -> Vec<F> {
-    let n = f.len();
+// z_1 = 1, z_i+1 = z_i * (1 + β)(γ + f_i)(γ(1 + β) + t_i + β t_i+1) / (γ(1 + β) + h1_i + β h1_i+1)(γ(1 + β) + h2_i + β h2_i+1)
+fn compute_z<F: FftField>(β: F, y: F, f: &[F], t: &[F], h1: &[F], h2: &[F]) -> Vec<F> {
+    let n = t.len();
     let one_β = F::one() + β;
     let mut z = vec![F::one()];
     let mut f_prev = F::one();
     let mut s_prev = F::one();
-    for i in 2..n {
-        let fi = compute_f(i, one_β, β, y, &f, &t);
-        let gi = compute_g(i, one_β, β, y, &h1, &h2);
+    for i in 0..n - 1 {
+        let fi = compute_f(i, one_β, β, y, f, t);
+        let gi = compute_g(i, one_β, β, y, h1, h2);
         f_prev *= fi;
         s_prev *= gi;
         let zi = f_prev / s_prev;
         z.push(zi)
     }
-    z.push(F::one());
+    debug_assert_eq!(z[n - 1], F::one());
     z
 }
 
-fn compute_f<F: FftField>(i: usize, one_β: F, β: F, y: F, f: &Vec<F>, t: &Vec<F>) -> F {
-    let left = y + f[i];
+fn compute_f<F: FftField>(i: usize, one_β: F, β: F, y: F, f: &[F], t: &[F]) -> F {
+    let left = one_β * (y + f[i]);
     let right = randomly_linear_combination(one_β, β, y, t[i], t[i + 1]);
     left * right
 }
 
-fn compute_g<F: FftField>(i: usize, one_β: F, β: F, y: F, h1: &Vec<F>, h2: &Vec<F>) -> F {
+fn compute_g<F: FftField>(i: usize, one_β: F, β: F, y: F, h1: &[F], h2: &[F]) -> F {
     let left = randomly_linear_combination(one_β, β, y, h1[i], h1[i + 1]);
     let right = randomly_linear_combination(one_β, β, y, h2[i], h2[i + 1]);
     left * right
 }
 
+// q(x) = (L_1(x)(z(x) - 1)
+//      + δ (x - g^n)(z(x)(1 + β)(γ + f(x))(γ(1 + β) + t(x) + β t(gx))
+//                   - z(gx)(γ(1 + β) + h1(x) + β h1(gx))(γ(1 + β) + h2(x) + β h2(gx)))
+//      + δ^2 L_n(x)(h1(x) - h2(gx))
+//      + δ^3 L_n(x)(z(x) - 1)) / (x^n - 1)
+#[allow(clippy::too_many_arguments)]
 fn compute_q<F: FftField>(
-    f: Polynomial<F>,
-    t: Polynomial<F>,
-    h1: Polynomial<F>,
-    h2: Polynomial<F>,
-    z: Polynomial<F>,
+    domain: &EvaluationDomain<F>,
+    f: &DensePolynomial<F>,
+    t: &DensePolynomial<F>,
+    h1: &DensePolynomial<F>,
+    h2: &DensePolynomial<F>,
+    z: &DensePolynomial<F>,
     β: F,
     y: F,
-) -> F {
+    δ: F,
+) -> DensePolynomial<F> {
+    let n = domain.size();
+    // numerator degree is at most 3n + 7 with blinding so 4n points determine it
+    // and Z_H vanishes on H so evaluate on coset g * H' instead
+    let extended = domain.extended(2);
+    let vanishing_inverses = domain.coset_vanishing_inverses(&extended);
+    let one_β = F::one() + β;
+    let g_last = domain.generator().pow(n as u64 - 1);
+    let l_first = domain.lagrange_basis(0);
+    let l_last = domain.lagrange_basis(n - 1);
+    let [f, t, h1, h2, z, l_first, l_last] = [f, t, h1, h2, z, &l_first, &l_last]
+        .map(|poly| extended.fft().coset_dft(poly.clone()).into_evals());
+
+    let quotient = extended
+        .elements()
+        .enumerate()
+        .map(|(i, x)| {
+            let x = F::MULTIPLICATIVE_GENERATOR * x;
+            // gx is four points ahead on the extended domain
+            let j = (i + 4) % (4 * n);
+            let first = l_first[i] * (z[i] - F::one());
+            let grand_product = (x - g_last)
+                * (z[i]
+                    * one_β
+                    * (y + f[i])
+                    * randomly_linear_combination(one_β, β, y, t[i], t[j])
+                    - z[j]
+                        * randomly_linear_combination(one_β, β, y, h1[i], h1[j])
+                        * randomly_linear_combination(one_β, β, y, h2[i], h2[j]));
+            let overlap = l_last[i] * (h1[i] - h2[j]);
+            let last = l_last[i] * (z[i] - F::one());
+            let numerator = first + δ * (grand_product + δ * (overlap + δ * last));
+            numerator * vanishing_inverses[i % 4]
+        })
+        .collect();
+    let quotient = extended
+        .fft()
+        .coset_idft(Evaluations::coset(quotient, 4 * n));
+    DensePolynomial::trimmed(quotient.coeffs)
 }
 
 // λ(1 + β) + a_i + β a_i_1
@@ -98,11 +556,56 @@ fn randomly_linear_combination<F: FftField>(one_β: F, β: F, y: F, a_i: F, a_i1
 
 #[cfg(test)]
 mod tests {
-    use super::table::XORTable;
-    use bls_12_381::Fr as Scalar;
+    use super::proof::LookupProof;
+    use super::table::{compress_columns, FunctionTable, LookupTable, MultiTable, RangeTable};
+    use super::{domain_size, sort_by_table, Lookup, LookupError};
+    use crate::{
+        fft::{Fft, FftStrategy},
+        kate_commitment::KateCommitment,
+    };
+    use bls_12_381::{Fr as Scalar, G1Affine as G1};
+    use ec_pairing::TatePairing;
     use rand::rngs::OsRng;
     use rand::{thread_rng, Rng};
-    use zkstd::common::{Group, PrimeField};
+    use zkstd::behave::CurveGroup;
+    use zkstd::common::{FftField, Group, PrimeField};
+
+    fn xor_witness<F: FftField>(range: u64, rows: usize) -> Lookup<F> {
+        let (mut a, mut b, mut c) = (Vec::new(), Vec::new(), Vec::new());
+        for _ in 0..rows {
+            let x = thread_rng().gen_range(0..range);
+            let y = thread_rng().gen_range(0..range);
+            a.push(F::from(x));
+            b.push(F::from(y));
+            c.push(F::from(x ^ y));
+        }
+        Lookup::new(vec![a, b, c])
+    }
+
+    // proves over 2^k powers srs with basis of padded domain and checks the proof
+    fn prove_and_verify(
+        lookup: &Lookup<Scalar>,
+        table: &impl LookupTable<Scalar>,
+        k: usize,
+    ) -> (
+        KateCommitment<TatePairing>,
+        usize,
+        Vec<G1>,
+        LookupProof<TatePairing>,
+    ) {
+        let mut pp = KateCommitment::<TatePairing>::new(k, Scalar::random(OsRng));
+        let n = domain_size::<Scalar>(lookup.len(), table.len()).unwrap();
+        pp.precompute_lagrange(&Fft::with_size(n, FftStrategy::default()));
+        let table_commitments = table.commit(&pp, n).unwrap();
+        let proof = lookup.prove(&pp, table).unwrap();
+        assert!(Lookup::verify(
+            &pp.verifier_key(),
+            n,
+            &table_commitments,
+            &proof
+        ));
+        (pp, n, table_commitments, proof)
+    }
 
     fn witness_vectors<F: PrimeField>(range: u64, alpha: F) -> Vec<F> {
         let i = 24;
@@ -120,20 +623,20 @@ mod tests {
         F::from(a) + alpha * F::from(b) + alpha.square() * F::from(c)
     }
 
-    fn s<F: PrimeField>(f: &Vec<F>, t: &Vec<F>) -> Vec<F> {
-        let mut s = [f.clone(), t.clone()].concat();
+    fn s<F: PrimeField>(f: &[F], t: &[F]) -> Vec<F> {
+        let mut s = [f, t].concat();
         s.sort();
         s
     }
 
     // get difference vectors
-    fn diff<F: PrimeField>(s: &Vec<F>) -> Vec<F> {
+    fn diff<F: PrimeField>(s: &[F]) -> Vec<F> {
         (0..s.len() - 1).map(|i| s[i + 1] - s[i]).collect()
     }
 
     // check a ⊂ b
-    fn multiset_check<F: PrimeField>(a: &Vec<F>, b: &Vec<F>) -> bool {
-        a.iter().all(|vector: &F| b.contains(&vector))
+    fn multiset_check<F: PrimeField>(a: &[F], b: &[F]) -> bool {
+        a.iter().all(|vector: &F| b.contains(vector))
     }
 
     #[test]
@@ -142,7 +645,7 @@ mod tests {
         let range = 1 << bit_length;
         let alpha = Scalar::random(OsRng);
         let mut witness_vectors = witness_vectors(range, alpha);
-        let xor_table = XORTable::<Scalar>::precompute();
+        let xor_table = FunctionTable::<Scalar>::xor(bit_length);
         let mut t = xor_table.compress(alpha);
 
         // naive multset check
@@ -160,4 +663,377 @@ mod tests {
         // s ⊂ t
         assert!(multiset_check(&s_prime, &t_2prime));
     }
+
+    #[test]
+    fn lookup_verify_test() {
+        let bit_length = 4;
+        let lookup = xor_witness::<Scalar>(1 << bit_length, 24);
+        let xor_table = FunctionTable::<Scalar>::xor(bit_length);
+        let (pp, n, table_commitments, proof) = prove_and_verify(&lookup, &xor_table, 9);
+
+        // prover fft does not change proof
+        let radix4 = lookup.clone().with_strategy(FftStrategy::Radix4 {
+            parallel_threshold: 16,
+        });
+        let radix4_proof = radix4.prove(&pp, &xor_table).unwrap();
+        assert_eq!(radix4_proof.commitments.q, proof.commitments.q);
+        assert!(Lookup::verify(
+            &pp.verifier_key(),
+            n,
+            &table_commitments,
+            &radix4_proof
+        ));
+
+        let mut invalid_proof = proof.clone();
+        invalid_proof.evaluations.q += Scalar::one();
+        assert!(!Lookup::verify(
+            &pp.verifier_key(),
+            n,
+            &table_commitments,
+            &invalid_proof
+        ));
+
+        let mut invalid_proof = proof.clone();
+        invalid_proof.commitments.z = G1::ADDITIVE_GENERATOR;
+        assert!(!Lookup::verify(
+            &pp.verifier_key(),
+            n,
+            &table_commitments,
+            &invalid_proof
+        ));
+
+        // α depends on every column commitment
+        let mut invalid_proof = proof.clone();
+        invalid_proof.commitments.f.swap(0, 1);
+        assert!(!Lookup::verify(
+            &pp.verifier_key(),
+            n,
+            &table_commitments,
+            &invalid_proof
+        ));
+        assert!(!Lookup::verify(
+            &pp.verifier_key(),
+            n,
+            &table_commitments[..2],
+            &proof
+        ));
+
+        // sizes without a domain are rejected rather than panicking
+        for n in [0, 40, 1 << 40] {
+            assert!(!Lookup::verify(
+                &pp.verifier_key(),
+                n,
+                &table_commitments,
+                &proof
+            ));
+        }
+    }
+
+    #[test]
+    fn range_lookup_test() {
+        let a = (0..24)
+            .map(|_| Scalar::from(thread_rng().gen_range(0..256)))
+            .collect::<Vec<_>>();
+        let lookup = Lookup::new(vec![a]);
+        prove_and_verify(&lookup, &RangeTable::<Scalar>::new(8), 9);
+    }
+
+    // table from raw columns
+    struct TestTable(Vec<Vec<Scalar>>);
+
+    impl LookupTable<Scalar> for TestTable {
+        fn columns(&self) -> &[Vec<Scalar>] {
+            &self.0
+        }
+    }
+
+    const SBOXES: [[u64; 16]; 2] = [
+        [12, 5, 6, 11, 9, 0, 10, 13, 3, 14, 15, 8, 4, 7, 1, 2],
+        [14, 4, 13, 1, 2, 15, 11, 8, 3, 10, 6, 12, 5, 9, 0, 7],
+    ];
+
+    fn sbox_row(tag: usize, x: usize) -> [u64; 4] {
+        let y = SBOXES[tag][x];
+        [tag as u64 + 1, x as u64, y, x as u64 ^ y]
+    }
+
+    #[test]
+    fn vector_lookup_test() {
+        let rows = (0..2)
+            .flat_map(|tag| (0..16).map(move |x| sbox_row(tag, x)))
+            .collect::<Vec<_>>();
+        // selector tag, input, output and its xor for two 4 bit s-boxes
+        let table = TestTable(
+            (0..4)
+                .map(|i| rows.iter().map(|row| Scalar::from(row[i])).collect())
+                .collect(),
+        );
+        let witness = (0..40)
+            .map(|_| sbox_row(thread_rng().gen_range(0..2), thread_rng().gen_range(0..16)))
+            .collect::<Vec<_>>();
+        let columns = (0..4)
+            .map(|i| witness.iter().map(|row| Scalar::from(row[i])).collect())
+            .collect::<Vec<_>>();
+        let lookup = Lookup::new(columns.clone());
+        let (pp, ..) = prove_and_verify(&lookup, &table, 8);
+
+        // three columns of four column table
+        let narrow = Lookup::new(columns[..3].to_vec());
+        assert_eq!(
+            narrow.prove(&pp, &table).unwrap_err(),
+            LookupError::WidthMismatch {
+                witness: 3,
+                table: 4
+            }
+        );
+    }
+
+    #[test]
+    fn sort_by_table_test() {
+        let t = [5, 3, 9, 3, 1, 1].map(Scalar::from);
+        let f = [1, 9, 5, 1, 3].map(Scalar::from);
+        let s = sort_by_table(&f, &t).unwrap();
+        assert_eq!(s, [5, 5, 3, 3, 9, 9, 3, 1, 1, 1, 1].map(Scalar::from));
+        // every adjacent pair is either repeated or adjacent in t
+        let t_pairs = t.windows(2).collect::<Vec<_>>();
+        assert!(s
+            .windows(2)
+            .all(|pair| pair[0] == pair[1] || t_pairs.contains(&pair)));
+
+        let f = [1, 9, 4, 1].map(Scalar::from);
+        assert_eq!(
+            sort_by_table(&f, &t),
+            Err(LookupError::NotInTable { row: 2 })
+        );
+    }
+
+    #[test]
+    fn not_in_table_test() {
+        let r = Scalar::random(OsRng);
+        let mut pp = KateCommitment::<TatePairing>::new(6, r);
+        // 20 rows and 16 table rows fit in 24 points
+        pp.precompute_lagrange(&Fft::with_size(24, FftStrategy::default()));
+        let xor_table = FunctionTable::<Scalar>::xor(2);
+        let mut lookup = xor_witness::<Scalar>(4, 20);
+        lookup.columns[2][7] += Scalar::one();
+        assert_eq!(
+            lookup.prove(&pp, &xor_table).unwrap_err(),
+            LookupError::NotInTable { row: 7 }
+        );
+    }
+
+    #[test]
+    fn hiding_test() {
+        let xor_table = FunctionTable::<Scalar>::xor(2);
+        let lookup = xor_witness::<Scalar>(4, 20);
+        let (pp, n, table_commitments, plain) = prove_and_verify(&lookup, &xor_table, 7);
+
+        let proof = lookup.prove_hiding(&pp, &xor_table, OsRng).unwrap();
+        assert!(Lookup::verify(
+            &pp.verifier_key(),
+            n,
+            &table_commitments,
+            &proof
+        ));
+
+        // same witness gives unrelated commitments and evaluations
+        let other = lookup.prove_hiding(&pp, &xor_table, OsRng).unwrap();
+        assert_ne!(proof.commitments.f, plain.commitments.f);
+        assert_ne!(proof.commitments.z, other.commitments.z);
+        assert_ne!(proof.evaluations.f, other.evaluations.f);
+
+        let mut invalid_proof = proof;
+        invalid_proof.evaluations.h1 += Scalar::one();
+        assert!(!Lookup::verify(
+            &pp.verifier_key(),
+            n,
+            &table_commitments,
+            &invalid_proof
+        ));
+
+        let small = KateCommitment::<TatePairing>::new(5, Scalar::random(OsRng));
+        assert_eq!(
+            lookup.prove_hiding(&small, &xor_table, OsRng).unwrap_err(),
+            LookupError::InsufficientDegree {
+                available: 32,
+                required: 55
+            }
+        );
+    }
+
+    #[test]
+    fn padding_test() {
+        assert_eq!(domain_size::<Scalar>(0, 1), Ok(8));
+        assert_eq!(domain_size::<Scalar>(10, 3), Ok(12));
+        assert_eq!(domain_size::<Scalar>(63, 64), Ok(64));
+        assert_eq!(domain_size::<Scalar>(64, 64), Ok(96));
+        assert_eq!(domain_size::<Scalar>(0, (1 << 16) + 1), Ok(3 << 15));
+        // 4n extended domain has at most 3 * 2^32 points
+        assert_eq!(domain_size::<Scalar>((3 << 30) - 1, 1), Ok(3 << 30));
+        assert_eq!(
+            domain_size::<Scalar>(3 << 30, 1),
+            Err(LookupError::DomainTooLarge {
+                size: (3 << 30) + 1,
+                max: 3 << 30
+            })
+        );
+        assert_eq!(
+            domain_size::<Scalar>(1 << 32, 1),
+            Err(LookupError::DomainTooLarge {
+                size: (1 << 32) + 1,
+                max: 3 << 30
+            })
+        );
+
+        let xor_table = FunctionTable::<Scalar>::xor(2);
+        // |f| = |t|, |f| < |t| and |f| > |t|
+        for rows in [16, 3, 50] {
+            prove_and_verify(&xor_witness::<Scalar>(4, rows), &xor_table, 7);
+        }
+    }
+
+    #[test]
+    fn setup_error_test() {
+        let r = Scalar::random(OsRng);
+        let xor_table = FunctionTable::<Scalar>::xor(2);
+        let lookup = xor_witness::<Scalar>(4, 20);
+
+        let pp = KateCommitment::<TatePairing>::new(5, r);
+        assert_eq!(
+            lookup.prove(&pp, &xor_table).unwrap_err(),
+            LookupError::InsufficientDegree {
+                available: 32,
+                required: 46
+            }
+        );
+        let mut pp = KateCommitment::<TatePairing>::new(6, r);
+        assert_eq!(
+            lookup.prove(&pp, &xor_table).unwrap_err(),
+            LookupError::LagrangeBasisMismatch {
+                domain: 24,
+                basis: 0
+            }
+        );
+        pp.precompute_lagrange(&Fft::new(6));
+        assert_eq!(
+            lookup.prove(&pp, &xor_table).unwrap_err(),
+            LookupError::LagrangeBasisMismatch {
+                domain: 24,
+                basis: 64
+            }
+        );
+        let empty = TestTable(vec![Vec::new(); 3]);
+        assert_eq!(
+            lookup.prove(&pp, &empty).unwrap_err(),
+            LookupError::EmptyTable
+        );
+    }
+
+    #[test]
+    fn multi_table_test() {
+        let xor_table = FunctionTable::<Scalar>::xor(2);
+        let and_table = FunctionTable::<Scalar>::and(2);
+        let range_table = RangeTable::<Scalar>::new(5);
+        let table = MultiTable::new(&[&xor_table, &and_table, &range_table]);
+        assert_eq!(table.width(), 4);
+        assert_eq!(table.len(), 16 + 16 + 32);
+
+        let binary = |op: fn(u64, u64) -> u64| {
+            let (a, b): (Vec<_>, Vec<_>) = (0..10)
+                .map(|_| (thread_rng().gen_range(0..4), thread_rng().gen_range(0..4)))
+                .unzip();
+            let c = a
+                .iter()
+                .zip(b.iter())
+                .map(|(a, b)| op(*a, *b))
+                .collect::<Vec<_>>();
+            Lookup::new(
+                [a, b, c]
+                    .map(|column| column.into_iter().map(Scalar::from).collect())
+                    .to_vec(),
+            )
+        };
+        let xor = binary(|a, b| a ^ b);
+        let and = binary(|a, b| a & b);
+        let range = Lookup::new(vec![(0..10)
+            .map(|_| Scalar::from(thread_rng().gen_range(0..32)))
+            .collect()]);
+        let lookup = table.route(&[(0, &xor), (1, &and), (2, &range)]).unwrap();
+        assert_eq!(lookup.len(), 30);
+
+        prove_and_verify(&lookup, &table, 8);
+
+        // and rows are not in xor table
+        let alpha = Scalar::random(OsRng);
+        let misrouted = table.route(&[(0, &and)]).unwrap();
+        let t = table.compress(alpha);
+        assert!(!compress_columns(&misrouted.columns, alpha)
+            .iter()
+            .all(|f| t.contains(f)));
+        assert_eq!(
+            table.route(&[(0, &range)]).unwrap_err(),
+            LookupError::WidthMismatch {
+                witness: 1,
+                table: 3
+            }
+        );
+        assert_eq!(
+            table.route(&[(1, &and), (3, &xor)]).unwrap_err(),
+            LookupError::UnknownTag { tag: 3 }
+        );
+    }
+
+    #[test]
+    fn wide_xor_test() {
+        let (width, bit_length) = (32, 4);
+        let a = (0..24)
+            .map(|_| thread_rng().gen::<u32>() as u64)
+            .collect::<Vec<_>>();
+        let b = (0..24)
+            .map(|_| thread_rng().gen::<u32>() as u64)
+            .collect::<Vec<_>>();
+        let lookup = Lookup::<Scalar>::decompose_xor(&a, &b, width, bit_length).unwrap();
+        assert_eq!(lookup.len(), 24 * width / bit_length);
+
+        // limbs recompose to wide xor
+        let recompose = |limbs: &[Scalar]| {
+            limbs.iter().rev().fold(Scalar::zero(), |acc, limb| {
+                acc * Scalar::from(1 << bit_length) + limb
+            })
+        };
+        lookup.columns[2]
+            .chunks(width / bit_length)
+            .zip(a.iter().zip(b.iter()))
+            .for_each(|(limbs, (x, y))| assert_eq!(recompose(limbs), Scalar::from(x ^ y)));
+
+        prove_and_verify(&lookup, &FunctionTable::<Scalar>::xor(bit_length), 9);
+    }
+
+    #[test]
+    fn xor_operand_width_test() {
+        assert_eq!(
+            Lookup::<Scalar>::decompose_xor(&[0xff, 0x100], &[0, 0], 8, 4).unwrap_err(),
+            LookupError::OperandTooWide { row: 1, width: 8 }
+        );
+        assert_eq!(
+            Lookup::<Scalar>::decompose_xor(&[0, 0], &[0xff, 0x1ff], 8, 4).unwrap_err(),
+            LookupError::OperandTooWide { row: 1, width: 8 }
+        );
+        assert_eq!(
+            Lookup::<Scalar>::decompose_xor(&[1, 2], &[1], 8, 4).unwrap_err(),
+            LookupError::OperandCountMismatch { a: 2, b: 1 }
+        );
+    }
+
+    #[test]
+    fn xor_bit_length_test() {
+        for (width, bit_length) in [(64, 64), (8, 0), (10, 4), (72, 8)] {
+            assert_eq!(
+                Lookup::<Scalar>::decompose_xor(&[1], &[1], width, bit_length).unwrap_err(),
+                LookupError::InvalidLimb { width, bit_length }
+            );
+        }
+        // full width operands are split without range check
+        assert!(Lookup::<Scalar>::decompose_xor(&[u64::MAX], &[1], 64, 16).is_ok());
+    }
 }
@@ -0,0 +1,54 @@
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum LookupError {
+    WidthMismatch { witness: usize, table: usize },
+    EmptyTable,
+    NotInTable { row: usize },
+    DomainTooLarge { size: usize, max: usize },
+    InsufficientDegree { available: usize, required: usize },
+    LagrangeBasisMismatch { domain: usize, basis: usize },
+    UnknownTag { tag: usize },
+    OperandCountMismatch { a: usize, b: usize },
+    InvalidLimb { width: usize, bit_length: usize },
+    OperandTooWide { row: usize, width: usize },
+}
+
+impl fmt::Display for LookupError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::WidthMismatch { witness, table } => write!(
+                f,
+                "witness has {witness} columns but table has {table} columns"
+            ),
+            Self::EmptyTable => write!(f, "table has no rows"),
+            Self::NotInTable { row } => write!(f, "witness row {row} is not in table"),
+            Self::DomainTooLarge { size, max } => write!(
+                f,
+                "lookup needs {size} points but fft domain has at most {max}"
+            ),
+            Self::InsufficientDegree {
+                available,
+                required,
+            } => write!(
+                f,
+                "setup supports degree {available} but quotient has degree {required}"
+            ),
+            Self::LagrangeBasisMismatch { domain, basis } => write!(
+                f,
+                "lookup domain has {domain} points but lagrange basis has {basis}"
+            ),
+            Self::UnknownTag { tag } => write!(f, "no table is tagged with {tag}"),
+            Self::OperandCountMismatch { a, b } => {
+                write!(f, "xor has {a} left operands but {b} right operands")
+            }
+            Self::InvalidLimb { width, bit_length } => write!(
+                f,
+                "{width} bit operands can not be split into {bit_length} bit limbs"
+            ),
+            Self::OperandTooWide { row, width } => {
+                write!(f, "operand in row {row} exceeds {width} bits")
+            }
+        }
+    }
+}
@@ -0,0 +1,49 @@
+use zkstd::behave::{FftField, Pairing};
+
+use crate::kate_commitment::GwcProof;
+
+/// plookup proof sent from prover to verifier
+#[derive(Clone, Debug)]
+pub(crate) struct LookupProof<P: Pairing> {
+    pub(crate) commitments: Commitments<P>,
+    pub(crate) evaluations: ProofEvaluations<P::ScalarField>,
+    pub(crate) openings: GwcProof<P>,
+}
+
+/// commitments to prover polynomials
+#[derive(Clone, Debug)]
+pub(crate) struct Commitments<P: Pairing> {
+    // one per witness column as α is drawn after them
+    pub(crate) f: Vec<P::G1Affine>,
+    pub(crate) h1: P::G1Affine,
+    pub(crate) h2: P::G1Affine,
+    pub(crate) z: P::G1Affine,
+    pub(crate) q: P::G1Affine,
+}
+
+/// polynomial evaluations at ζ and ωζ
+#[derive(Clone, Debug)]
+pub(crate) struct ProofEvaluations<F> {
+    pub(crate) f: F,
+    pub(crate) t: F,
+    pub(crate) t_omega: F,
+    pub(crate) h1: F,
+    pub(crate) h1_omega: F,
+    pub(crate) h2: F,
+    pub(crate) h2_omega: F,
+    pub(crate) z: F,
+    pub(crate) z_omega: F,
+    pub(crate) q: F,
+}
+
+impl<F: FftField> ProofEvaluations<F> {
+    /// f, t, h1, h2, z and q at ζ
+    pub(crate) fn zeta(&self) -> [F; 6] {
+        [self.f, self.t, self.h1, self.h2, self.z, self.q]
+    }
+
+    /// t, h1, h2 and z at ωζ
+    pub(crate) fn zeta_omega(&self) -> [F; 4] {
+        [self.t_omega, self.h1_omega, self.h2_omega, self.z_omega]
+    }
+}
@@ -1,59 +1,493 @@
-use zkstd::common::PrimeField;
-
-pub(crate) struct XORTable<F: PrimeField> {
-    n: usize,
-    a: Vec<F>,
-    b: Vec<F>,
-    c: Vec<F>,
-}
-
-impl<F: PrimeField> XORTable<F> {
-    pub(crate) fn precompute() -> Self {
-        let bit_length = 4;
-        let n = 1 << bit_length;
-        let (mut a, mut b, mut c) = (Vec::new(), Vec::new(), Vec::new());
-        for i in 0..n {
-            for j in 0..n {
-                let k = i ^ j;
-                a.push(F::from(i as u64));
-                b.push(F::from(j as u64));
-                c.push(F::from(k as u64));
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use rayon::prelude::*;
+use zkstd::behave::Pairing;
+use zkstd::common::{Decode, Encode, FftField};
+
+use super::{Lookup, LookupError};
+use crate::{evaluations::Evaluations, kate_commitment::KateCommitment};
+
+/// table whose rows are looked up by plookup
+pub(crate) trait LookupTable<F: FftField> {
+    /// columns of equal length
+    fn columns(&self) -> &[Vec<F>];
+
+    /// cache for table commitments if table keeps one
+    fn commitment_cache(&self) -> Option<&CommitmentCache> {
+        None
+    }
+
+    fn len(&self) -> usize {
+        self.columns().first().map_or(0, Vec::len)
+    }
+
+    /// number of columns
+    fn width(&self) -> usize {
+        self.columns().len()
+    }
+
+    fn compress(&self, alpha: F) -> Vec<F> {
+        compress_columns(self.columns(), alpha)
+    }
+
+    /// compressed table in table order padded with the last element to n
+    fn preprocess(&self, alpha: F, n: usize) -> Result<Vec<F>, LookupError> {
+        padded(&self.compress(alpha), n)
+    }
+
+    /// commitments to every column padded with its last element to n
+    /// padding commutes with compression so the table commitment is Σ α^i [t_i]
+    fn commit<P: Pairing<ScalarField = F>>(
+        &self,
+        pp: &KateCommitment<P>,
+        n: usize,
+    ) -> Result<Vec<P::G1Affine>, LookupError>
+    where
+        Self: Sized,
+    {
+        let basis = pp.prover_key().lagrange_size();
+        if basis != n {
+            return Err(LookupError::LagrangeBasisMismatch { domain: n, basis });
+        }
+        let commit = || {
+            if self.len() == 0 {
+                return Err(LookupError::EmptyTable);
+            }
+            self.columns()
+                .iter()
+                .map(|column| Ok(pp.commit_lagrange(&Evaluations::new(padded(column, n)?, n))))
+                .collect()
+        };
+        match self.commitment_cache() {
+            Some(cache) => cache.get_or_commit(pp, n, commit),
+            None => commit(),
+        }
+    }
+}
+
+/// column commitments by setup and domain size
+#[derive(Debug, Default)]
+pub(crate) struct CommitmentCache(Mutex<HashMap<Vec<u8>, Vec<u8>>>);
+
+impl CommitmentCache {
+    fn get_or_commit<P: Pairing>(
+        &self,
+        pp: &KateCommitment<P>,
+        n: usize,
+        commit: impl FnOnce() -> Result<Vec<P::G1Affine>, LookupError>,
+    ) -> Result<Vec<P::G1Affine>, LookupError> {
+        // L_0(r) * G1 identifies both setup and domain
+        let Some(l_first) = pp.prover_key().lagrange_basis().first() else {
+            return commit();
+        };
+        let mut key = l_first.encode();
+        key.extend((n as u64).encode());
+        let mut cache = self.0.lock().unwrap();
+        match cache.get(&key) {
+            Some(bytes) => Ok(Vec::<P::G1Affine>::decode(&mut bytes.as_slice()).unwrap()),
+            None => {
+                let commitments = commit()?;
+                cache.insert(key, commitments.encode());
+                Ok(commitments)
             }
         }
-        Self { n, a, b, c }
+    }
+}
+
+// column padded with its last element to n
+fn padded<F: FftField>(column: &[F], n: usize) -> Result<Vec<F>, LookupError> {
+    let last = match column.last() {
+        Some(last) => *last,
+        None => return Err(LookupError::EmptyTable),
+    };
+    // truncated table would accept lookups against fewer rows
+    if n < column.len() {
+        return Err(LookupError::DomainTooLarge {
+            size: column.len(),
+            max: n,
+        });
+    }
+    let mut column = column.to_vec();
+    column.resize(n, last);
+    Ok(column)
+}
+
+/// (i, j, f(i, j)) for i, j < 2^bit_length where f(i, j) must also fit in bit_length bits
+#[derive(Debug, Default)]
+pub(crate) struct FunctionTable<F: FftField> {
+    columns: Vec<Vec<F>>,
+    cache: CommitmentCache,
+}
+
+impl<F: FftField> FunctionTable<F> {
+    pub(crate) fn new(bit_length: usize, f: impl Fn(u64, u64) -> u64 + Sync) -> Self {
+        // 2^(2 * bit_length) rows must fit in fft domain
+        assert!(bit_length > 0 && bit_length <= 16);
+        let mask = (1 << bit_length) - 1;
+        // row = i * 2^bit_length + j
+        let column = |value: &(dyn Fn(u64, u64) -> u64 + Sync)| {
+            (0..1u64 << (2 * bit_length))
+                .into_par_iter()
+                .map(|row| F::from(value(row >> bit_length, row & mask)))
+                .collect()
+        };
+        let columns = vec![
+            column(&|i, _| i),
+            column(&|_, j| j),
+            column(&|i, j| {
+                let value = f(i, j);
+                // truncated output would accept wrong witnesses
+                assert!(
+                    value >> bit_length == 0,
+                    "f({i}, {j}) exceeds {bit_length} bits"
+                );
+                value
+            }),
+        ];
+        Self {
+            columns,
+            cache: CommitmentCache::default(),
+        }
+    }
+
+    pub(crate) fn xor(bit_length: usize) -> Self {
+        Self::new(bit_length, |i, j| i ^ j)
+    }
+
+    pub(crate) fn and(bit_length: usize) -> Self {
+        Self::new(bit_length, |i, j| i & j)
+    }
+
+    pub(crate) fn or(bit_length: usize) -> Self {
+        Self::new(bit_length, |i, j| i | j)
+    }
+}
+
+/// (i, !i) for i < 2^bit_length
+#[derive(Debug, Default)]
+pub(crate) struct NotTable<F: FftField> {
+    columns: Vec<Vec<F>>,
+    cache: CommitmentCache,
+}
+
+impl<F: FftField> NotTable<F> {
+    pub(crate) fn new(bit_length: usize) -> Self {
+        Self {
+            columns: unary_columns(bit_length, |i| !i),
+            cache: CommitmentCache::default(),
+        }
+    }
+}
+
+/// i for i < 2^bit_length
+#[derive(Debug, Default)]
+pub(crate) struct RangeTable<F: FftField> {
+    columns: Vec<Vec<F>>,
+    cache: CommitmentCache,
+}
+
+impl<F: FftField> RangeTable<F> {
+    pub(crate) fn new(bit_length: usize) -> Self {
+        assert!(bit_length > 0 && bit_length <= 32);
+        let column = (0..1u64 << bit_length)
+            .into_par_iter()
+            .map(F::from)
+            .collect();
+        Self {
+            columns: vec![column],
+            cache: CommitmentCache::default(),
+        }
+    }
+}
+
+/// (i, i rotated left by shift) for bit_length bit i
+#[derive(Debug, Default)]
+pub(crate) struct RotationTable<F: FftField> {
+    columns: Vec<Vec<F>>,
+    cache: CommitmentCache,
+}
+
+impl<F: FftField> RotationTable<F> {
+    pub(crate) fn new(bit_length: usize, shift: usize) -> Self {
+        assert!(shift < bit_length);
+        let rotate = |i: u64| (i << shift) | (i >> ((bit_length - shift) % bit_length));
+        Self {
+            columns: unary_columns(bit_length, rotate),
+            cache: CommitmentCache::default(),
+        }
     }
 
-    pub(crate) fn compress(&self, alpha: F) -> Vec<F> {
-        let alpha2 = alpha.square();
-        self.a
+    /// rotation of a byte
+    pub(crate) fn byte(shift: usize) -> Self {
+        Self::new(8, shift)
+    }
+}
+
+/// tables concatenated with tag column so that one proof covers all of them
+/// narrower tables are padded with zero columns
+#[derive(Debug, Default)]
+pub(crate) struct MultiTable<F: FftField> {
+    // tag, then columns of widest table
+    columns: Vec<Vec<F>>,
+    widths: Vec<usize>,
+    cache: CommitmentCache,
+}
+
+impl<F: FftField> MultiTable<F> {
+    /// i th table is tagged with i
+    pub(crate) fn new(tables: &[&dyn LookupTable<F>]) -> Self {
+        let widths = tables.iter().map(|table| table.width()).collect::<Vec<_>>();
+        let width = widths.iter().copied().max().unwrap_or(0);
+        let mut columns = vec![Vec::new(); width + 1];
+        tables
             .iter()
-            .zip(self.b.iter())
-            .zip(self.c.iter())
-            .map(|((t1, t2), t3)| *t1 + alpha * t2 + alpha2 * t3)
-            .collect()
+            .enumerate()
+            .for_each(|(tag, table)| append_tagged(&mut columns, tag, table.columns()));
+        Self {
+            columns,
+            widths,
+            cache: CommitmentCache::default(),
+        }
+    }
+
+    /// concatenate witnesses tagged with table they are looked up in
+    pub(crate) fn route(&self, lookups: &[(usize, &Lookup<F>)]) -> Result<Lookup<F>, LookupError> {
+        let mut columns = vec![Vec::new(); self.columns.len()];
+        for (tag, lookup) in lookups {
+            let width = match self.widths.get(*tag) {
+                Some(width) => *width,
+                None => return Err(LookupError::UnknownTag { tag: *tag }),
+            };
+            if lookup.width() != width {
+                return Err(LookupError::WidthMismatch {
+                    witness: lookup.width(),
+                    table: width,
+                });
+            }
+            append_tagged(&mut columns, *tag, &lookup.columns);
+        }
+        Ok(Lookup::new(columns))
     }
 }
 
+// append rows with tag column and zero padded columns
+fn append_tagged<F: FftField>(columns: &mut [Vec<F>], tag: usize, rows: &[Vec<F>]) {
+    let len = rows.first().map_or(0, Vec::len);
+    columns[0].extend(vec![F::from(tag as u64); len]);
+    columns[1..]
+        .iter_mut()
+        .enumerate()
+        .for_each(|(i, column)| match rows.get(i) {
+            Some(values) => column.extend(values),
+            None => column.extend(vec![F::zero(); len]),
+        });
+}
+
+/// c_0 + α c_1 + α^2 c_2 + ... for every row
+pub(crate) fn compress_columns<F: FftField>(columns: &[Vec<F>], alpha: F) -> Vec<F> {
+    let len = columns.first().map_or(0, Vec::len);
+    (0..len)
+        .into_par_iter()
+        .map(|i| {
+            columns
+                .iter()
+                .rev()
+                .fold(F::zero(), |acc, column| acc * alpha + column[i])
+        })
+        .collect()
+}
+
+// (i, f(i)) masked to bit_length bits
+fn unary_columns<F: FftField>(bit_length: usize, f: impl Fn(u64) -> u64 + Sync) -> Vec<Vec<F>> {
+    assert!(bit_length > 0 && bit_length <= 32);
+    let mask = (1 << bit_length) - 1;
+    let (input, output) = (0..1u64 << bit_length)
+        .into_par_iter()
+        .map(|i| (F::from(i), F::from(f(i) & mask)))
+        .unzip();
+    vec![input, output]
+}
+
+macro_rules! impl_lookup_table {
+    ($($table:ident),*) => {
+        $(
+            impl<F: FftField> LookupTable<F> for $table<F> {
+                fn columns(&self) -> &[Vec<F>] {
+                    &self.columns
+                }
+
+                fn commitment_cache(&self) -> Option<&CommitmentCache> {
+                    Some(&self.cache)
+                }
+            }
+        )*
+    };
+}
+
+impl_lookup_table!(
+    FunctionTable,
+    NotTable,
+    RangeTable,
+    RotationTable,
+    MultiTable
+);
+
 #[cfg(test)]
 mod tests {
-    use super::XORTable;
-    use bls_12_381::Fr as Scalar;
+    use super::{
+        padded, FunctionTable, LookupError, LookupTable, NotTable, RangeTable, RotationTable,
+    };
+    use crate::{evaluations::Evaluations, fft::Fft, kate_commitment::KateCommitment};
+
+    use bls_12_381::{Fr as Scalar, G1Affine, G1Projective as G1};
+    use ec_pairing::TatePairing;
+    use rand::rngs::OsRng;
+    use zkstd::behave::{CurveGroup, Group, PrimeField};
+
+    type BinaryOp = fn(u64, u64) -> u64;
+
+    fn rows(table: &impl LookupTable<Scalar>) -> Vec<Vec<Scalar>> {
+        (0..table.len())
+            .map(|i| table.columns().iter().map(|column| column[i]).collect())
+            .collect()
+    }
 
     #[test]
     fn table_generation_test() {
-        let bit_length = 4;
-        let length = 1 << (bit_length * 2);
-        let xor_table = XORTable::<Scalar>::precompute();
-        assert_eq!(xor_table.a.len(), length);
-        assert_eq!(xor_table.b.len(), length);
-        assert_eq!(xor_table.c.len(), length);
-        for ((i, j), k) in xor_table
-            .a
-            .iter()
-            .zip(xor_table.b.iter())
-            .zip(xor_table.c.iter())
-        {
-            assert_eq!(i ^ j, *k)
+        for bit_length in [1, 4, 8] {
+            let length = 1 << (bit_length * 2);
+            let xor_table = FunctionTable::<Scalar>::xor(bit_length);
+            assert_eq!(xor_table.len(), length);
+            assert!(xor_table.columns().iter().all(|c| c.len() == length));
+            for row in rows(&xor_table) {
+                assert_eq!(row[0] ^ row[1], row[2])
+            }
         }
     }
+
+    #[test]
+    fn bitwise_table_test() {
+        let ops: [(FunctionTable<Scalar>, BinaryOp); 3] = [
+            (FunctionTable::and(3), |i, j| i & j),
+            (FunctionTable::or(3), |i, j| i | j),
+            (FunctionTable::new(3, |i, j| (i * j + 1) & 7), |i, j| {
+                (i * j + 1) & 7
+            }),
+        ];
+        for (table, op) in ops {
+            let expected = (0..8)
+                .flat_map(|i| (0..8).map(move |j| (i, j, op(i, j))))
+                .map(|(i, j, k)| vec![Scalar::from(i), Scalar::from(j), Scalar::from(k)])
+                .collect::<Vec<_>>();
+            assert_eq!(rows(&table), expected);
+        }
+
+        let not = NotTable::<Scalar>::new(4);
+        let expected = (0..16)
+            .map(|i| vec![Scalar::from(i), Scalar::from(15 - i)])
+            .collect::<Vec<_>>();
+        assert_eq!(rows(&not), expected);
+
+        let range = RangeTable::<Scalar>::new(5);
+        assert_eq!(
+            range.columns()[0],
+            (0..32).map(Scalar::from).collect::<Vec<_>>()
+        );
+
+        let rotation = RotationTable::<Scalar>::byte(3);
+        let expected = (0..=255u8)
+            .map(|i| {
+                vec![
+                    Scalar::from(i as u64),
+                    Scalar::from(i.rotate_left(3) as u64),
+                ]
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(rows(&rotation), expected);
+        let identity = RotationTable::<Scalar>::byte(0);
+        assert!(rows(&identity).iter().all(|row| row[0] == row[1]));
+    }
+
+    #[test]
+    #[should_panic(expected = "exceeds 4 bits")]
+    fn wide_output_test() {
+        FunctionTable::<Scalar>::new(4, |i, j| i * j);
+    }
+
+    #[test]
+    fn compress_test() {
+        let alpha = Scalar::random(OsRng);
+        let table = FunctionTable::<Scalar>::xor(2);
+        let compressed = table.compress(alpha);
+        rows(&table)
+            .iter()
+            .zip(compressed.iter())
+            .for_each(|(row, t)| assert_eq!(row[0] + alpha * row[1] + alpha.square() * row[2], *t));
+        // single column is not compressed
+        let range = RangeTable::<Scalar>::new(3);
+        assert_eq!(range.compress(alpha), range.columns()[0]);
+    }
+
+    #[test]
+    fn commitment_cache_test() {
+        let r = Scalar::random(OsRng);
+        let mut pp = KateCommitment::<TatePairing>::new(5, r);
+        pp.precompute_lagrange(&Fft::new(5));
+        let table = FunctionTable::<Scalar>::and(2);
+        let commitments = table.commit(&pp, 32).unwrap();
+        assert_eq!(table.cache.0.lock().unwrap().len(), 1);
+        assert_eq!(table.commit(&pp, 32).unwrap(), commitments);
+        assert_eq!(table.cache.0.lock().unwrap().len(), 1);
+
+        // α combination of column commitments commits to preprocessed table
+        let alpha = Scalar::random(OsRng);
+        let compressed = commitments
+            .iter()
+            .rev()
+            .fold(G1::ADDITIVE_IDENTITY, |acc, c| acc * alpha + *c);
+        assert_eq!(
+            G1Affine::from(compressed),
+            pp.commit_lagrange(&Evaluations::new(table.preprocess(alpha, 32).unwrap(), 32))
+        );
+
+        // another setup is committed separately
+        let mut other = KateCommitment::<TatePairing>::new(5, Scalar::random(OsRng));
+        other.precompute_lagrange(&Fft::new(5));
+        let column = Evaluations::new(padded(&table.columns()[2], 32).unwrap(), 32);
+        assert_eq!(
+            table.commit(&other, 32).unwrap()[2],
+            other.commit_lagrange(&column)
+        );
+        assert_eq!(table.cache.0.lock().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn preprocess_error_test() {
+        let alpha = Scalar::random(OsRng);
+        let range = RangeTable::<Scalar>::new(4);
+        assert_eq!(range.preprocess(alpha, 16).unwrap(), range.compress(alpha));
+        assert_eq!(
+            range.preprocess(alpha, 8),
+            Err(LookupError::DomainTooLarge { size: 16, max: 8 })
+        );
+        let empty = RangeTable::<Scalar> {
+            columns: vec![Vec::new()],
+            cache: Default::default(),
+        };
+        assert_eq!(empty.preprocess(alpha, 8), Err(LookupError::EmptyTable));
+
+        // failed commitment is not cached
+        let mut pp = KateCommitment::<TatePairing>::new(3, Scalar::random(OsRng));
+        pp.precompute_lagrange(&Fft::new(3));
+        assert!(range.commit(&pp, 8).is_err());
+        assert_eq!(
+            range.commit(&pp, 16),
+            Err(LookupError::LagrangeBasisMismatch {
+                domain: 16,
+                basis: 8
+            })
+        );
+        assert!(range.cache.0.lock().unwrap().is_empty());
+    }
 }
@@ -0,0 +1,257 @@
+//! multi scalar multiplication
+//! https://jbootle.github.io/Misc/pippenger.pdf
+use rayon::prelude::*;
+use zkstd::behave::{
+    CurveAffine, CurveExtended, CurveGroup, FftField, Group, PrimeField, WeierstrassAffine,
+};
+use zkstd::common::Encode;
+
+use crate::utils::batch_inversion;
+
+const CHUNK_SIZE: usize = 1 << 10;
+
+/// Σ scalar_i * base_i by pippenger bucket method where bases may outnumber scalars
+pub(crate) fn msm<C: CurveAffine>(bases: &[C], scalars: &[C::Scalar]) -> C::Extended {
+    assert!(
+        scalars.len() <= bases.len(),
+        "{} scalars for {} bases",
+        scalars.len(),
+        bases.len()
+    );
+    let c = window_size(scalars.len());
+    let scalars = scalars
+        .par_iter()
+        .map(|scalar| scalar.to_raw_bytes())
+        .collect::<Vec<_>>();
+    let bits = scalars.first().map_or(0, |bytes| bytes.len() * 8);
+
+    // each window sums up Σ digit_i * base_i
+    let window_sums = (0..bits)
+        .step_by(c)
+        .collect::<Vec<_>>()
+        .into_par_iter()
+        .map(|offset| {
+            let mut buckets = vec![C::Extended::ADDITIVE_IDENTITY; (1 << c) - 1];
+            scalars.iter().zip(bases.iter()).for_each(|(scalar, base)| {
+                let digit = window_digit(scalar, offset, c);
+                if digit != 0 {
+                    buckets[digit - 1] += *base;
+                }
+            });
+            // Σ i * bucket_i by running sum
+            let (_, sum) = buckets.into_iter().rev().fold(
+                (
+                    C::Extended::ADDITIVE_IDENTITY,
+                    C::Extended::ADDITIVE_IDENTITY,
+                ),
+                |(running, sum), bucket| {
+                    let running = running + bucket;
+                    (running, sum + running)
+                },
+            );
+            sum
+        })
+        .collect::<Vec<_>>();
+
+    // combine windows from most significant
+    window_sums
+        .into_iter()
+        .rev()
+        .fold(C::Extended::ADDITIVE_IDENTITY, |acc, sum| {
+            (0..c).fold(acc, |acc, _| acc.double()) + sum
+        })
+}
+
+/// scalar_i * base for every scalar by precomputed windows of base
+pub(crate) fn fixed_base_mul<C: WeierstrassAffine>(base: C, scalars: &[C::Scalar]) -> Vec<C> {
+    let c = if scalars.len() < CHUNK_SIZE { 4 } else { 8 };
+    let bits = C::Scalar::zero().to_raw_bytes().len() * 8;
+
+    // d * 2^(c * j) * base for d < 2^c in j th window
+    let mut window_base = base.to_extended();
+    let table = (0..bits)
+        .step_by(c)
+        .map(|_| {
+            let row = (0..1 << c)
+                .scan(C::Extended::ADDITIVE_IDENTITY, |acc, _| {
+                    let tmp = *acc;
+                    *acc += window_base;
+                    Some(tmp)
+                })
+                .collect::<Vec<_>>();
+            window_base = (0..c).fold(window_base, |acc, _| acc.double());
+            batch_normalize(&row)
+        })
+        .collect::<Vec<Vec<C>>>();
+
+    let points = scalars
+        .par_iter()
+        .map(|scalar| {
+            let bytes = scalar.to_raw_bytes();
+            table
+                .iter()
+                .enumerate()
+                .fold(C::Extended::ADDITIVE_IDENTITY, |acc, (j, row)| {
+                    acc + row[window_digit(&bytes, j * c, c)]
+                })
+        })
+        .collect::<Vec<_>>();
+    batch_normalize(&points)
+}
+
+/// projective to affine with single inversion per chunk
+pub(crate) fn batch_normalize<C: WeierstrassAffine>(points: &[C::Extended]) -> Vec<C> {
+    points
+        .par_chunks(CHUNK_SIZE)
+        .flat_map_iter(|chunk| {
+            let mut z_invs = chunk.iter().map(|point| point.get_z()).collect::<Vec<_>>();
+            batch_inversion(&mut z_invs);
+            chunk
+                .iter()
+                .zip(z_invs)
+                .map(|(point, z_inv)| {
+                    if z_inv.is_zero() {
+                        C::ADDITIVE_IDENTITY
+                    } else {
+                        affine_from_xy(point.get_x() * z_inv, point.get_y() * z_inv)
+                    }
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+// affine point exposes no constructor so build it from its x, y and infinity flag encoding
+// the layout is pinned by affine_layout_test for every curve this crate normalizes
+fn affine_from_xy<C: WeierstrassAffine>(x: C::Range, y: C::Range) -> C {
+    let mut bytes = x.encode();
+    bytes.extend(y.encode());
+    bytes.extend(false.encode());
+    C::decode(&mut bytes.as_slice()).expect("affine point is encoded as x, y and infinity flag")
+}
+
+// bucket window bit length
+fn window_size(n: usize) -> usize {
+    if n < 32 {
+        3
+    } else {
+        (n as f64).ln().ceil() as usize
+    }
+}
+
+// little endian bits from offset to offset + c
+fn window_digit(bytes: &[u8], offset: usize, c: usize) -> usize {
+    (offset..(offset + c).min(bytes.len() * 8))
+        .rev()
+        .fold(0, |digit, i| {
+            (digit << 1) | ((bytes[i / 8] >> (i % 8)) & 1) as usize
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{affine_from_xy, batch_normalize, fixed_base_mul, msm};
+
+    use bls_12_381::{Fr as Scalar, G1Affine, G1Projective, G2Affine};
+    use rand::rngs::OsRng;
+    use zkstd::behave::{CurveGroup, Group, WeierstrassAffine};
+    use zkstd::common::Encode;
+
+    fn naive_msm(bases: &[G1Affine], scalars: &[Scalar]) -> G1Projective {
+        bases
+            .iter()
+            .zip(scalars.iter())
+            .fold(G1Projective::ADDITIVE_IDENTITY, |sum, (base, scalar)| {
+                sum + *base * *scalar
+            })
+    }
+
+    #[test]
+    fn msm_test() {
+        for n in [0, 1, 7, 33, 256] {
+            let bases = (0..n)
+                .map(|_| G1Affine::from(G1Affine::ADDITIVE_GENERATOR * Scalar::random(OsRng)))
+                .collect::<Vec<_>>();
+            let scalars = (0..n).map(|_| Scalar::random(OsRng)).collect::<Vec<_>>();
+            assert_eq!(msm(&bases, &scalars), naive_msm(&bases, &scalars));
+        }
+    }
+
+    #[test]
+    fn msm_edge_scalars_test() {
+        let bases = (0..8)
+            .map(|_| G1Affine::from(G1Affine::ADDITIVE_GENERATOR * Scalar::random(OsRng)))
+            .collect::<Vec<_>>();
+        let scalars = [
+            Scalar::zero(),
+            Scalar::one(),
+            -Scalar::one(),
+            Scalar::from(2),
+            Scalar::from(u64::MAX),
+            Scalar::zero(),
+            -Scalar::from(2),
+            Scalar::one(),
+        ];
+        assert_eq!(msm(&bases, &scalars), naive_msm(&bases, &scalars));
+        // bases longer than scalars
+        assert_eq!(msm(&bases, &scalars[..3]), naive_msm(&bases, &scalars[..3]));
+    }
+
+    #[test]
+    #[should_panic(expected = "9 scalars for 8 bases")]
+    fn msm_excess_scalars_test() {
+        let bases = vec![G1Affine::ADDITIVE_GENERATOR; 8];
+        let scalars = vec![Scalar::one(); 9];
+        msm(&bases, &scalars);
+    }
+
+    #[test]
+    fn batch_normalize_test() {
+        let points = (0..100)
+            .map(|i| {
+                if i % 9 == 0 {
+                    G1Projective::ADDITIVE_IDENTITY
+                } else {
+                    G1Affine::ADDITIVE_GENERATOR * Scalar::random(OsRng)
+                }
+            })
+            .collect::<Vec<_>>();
+        let normalized = batch_normalize::<G1Affine>(&points);
+        assert!(points
+            .iter()
+            .zip(normalized.iter())
+            .all(|(point, affine)| G1Affine::from(*point) == *affine));
+    }
+
+    #[test]
+    fn fixed_base_mul_test() {
+        let base = G1Affine::from(G1Affine::ADDITIVE_GENERATOR * Scalar::random(OsRng));
+        let mut scalars = (0..50).map(|_| Scalar::random(OsRng)).collect::<Vec<_>>();
+        scalars.extend([Scalar::zero(), Scalar::one(), -Scalar::one()]);
+        let points = fixed_base_mul(base, &scalars);
+        assert!(scalars
+            .iter()
+            .zip(points.iter())
+            .all(|(scalar, point)| G1Affine::from(base * *scalar) == *point));
+    }
+
+    fn assert_affine_layout<C: WeierstrassAffine>(point: C) {
+        let (x, y) = (point.get_x(), point.get_y());
+        let mut expected = x.encode();
+        expected.extend(y.encode());
+        expected.extend(point.is_identity().encode());
+        assert_eq!(point.encode(), expected);
+        if !point.is_identity() {
+            assert_eq!(affine_from_xy::<C>(x, y), point);
+        }
+    }
+
+    #[test]
+    fn affine_layout_test() {
+        let r = Scalar::random(OsRng);
+        assert_affine_layout(G1Affine::from(G1Affine::ADDITIVE_GENERATOR * r));
+        assert_affine_layout(G1Affine::ADDITIVE_IDENTITY);
+        assert_affine_layout(G2Affine::from(G2Affine::ADDITIVE_GENERATOR * r));
+        assert_affine_layout(G2Affine::ADDITIVE_IDENTITY);
+    }
+}
@@ -1,6 +1,8 @@
 use zkstd::behave::{CurveAffine, Group};
 use zkstd::common::RngCore;
 
+use crate::msm::msm;
+
 pub(crate) struct PedersenCommitment<C: CurveAffine> {
     g: C,
     h: C,
@@ -24,9 +26,34 @@ impl<C: CurveAffine> PedersenCommitment<C> {
     }
 }
 
+pub(crate) struct VectorPedersenCommitment<C: CurveAffine> {
+    g: Vec<C>,
+    h: C,
+}
+
+impl<C: CurveAffine> VectorPedersenCommitment<C> {
+    pub(crate) fn new(n: usize, mut r: impl RngCore) -> Self {
+        let g = (0..n)
+            .map(|_| (C::ADDITIVE_GENERATOR * C::Scalar::random(&mut r)).into())
+            .collect();
+        let h = (C::ADDITIVE_GENERATOR * C::Scalar::random(r)).into();
+        Self { g, h }
+    }
+
+    pub(crate) fn commit(&self, s: &[C::Scalar], r: impl RngCore) -> (C, C::Scalar) {
+        let t = C::Scalar::random(r);
+        let commitment = msm(&self.g, s) + self.h * t;
+        (commitment.into(), t)
+    }
+
+    pub(crate) fn open(&self, commitment: C, s: &[C::Scalar], t: C::Scalar) -> bool {
+        commitment.to_extended() == msm(&self.g, s) + self.h * t
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use super::PedersenCommitment;
+    use super::{PedersenCommitment, VectorPedersenCommitment};
 
     use bls_12_381::{Fr as Scalar, G1Affine as Point};
     use rand::rngs::OsRng;
@@ -39,4 +66,17 @@ mod tests {
         let (commitment, t) = params.commit(s, OsRng);
         assert!(params.open(commitment, s, t))
     }
+
+    #[test]
+    fn vector_perdersen_commitment_test() {
+        let n = 64;
+        let s = (0..n).map(|_| Scalar::random(OsRng)).collect::<Vec<_>>();
+        let params = VectorPedersenCommitment::<Point>::new(n, OsRng);
+        let (commitment, t) = params.commit(&s, OsRng);
+        assert!(params.open(commitment, &s, t));
+
+        let mut invalid_s = s;
+        invalid_s[0] += Scalar::one();
+        assert!(!params.open(commitment, &invalid_s, t))
+    }
 }
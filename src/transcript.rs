@@ -0,0 +1,78 @@
+//! Fiat-Shamir transcript
+//! absorbs prover messages with labels and squeezes challenges by blake2b
+use blake2b_simd::{Params, State};
+use zkstd::behave::{CurveAffine, FftField};
+
+#[derive(Clone)]
+pub(crate) struct Transcript {
+    state: State,
+}
+
+impl Transcript {
+    pub(crate) fn new(label: &'static [u8]) -> Self {
+        let mut transcript = Self {
+            state: Params::new().hash_length(64).to_state(),
+        };
+        transcript.append_message(b"dom-sep", label);
+        transcript
+    }
+
+    pub(crate) fn append_message(&mut self, label: &'static [u8], message: &[u8]) {
+        self.state.update(&(label.len() as u64).to_le_bytes());
+        self.state.update(label);
+        self.state.update(&(message.len() as u64).to_le_bytes());
+        self.state.update(message);
+    }
+
+    pub(crate) fn append_point<C: CurveAffine>(&mut self, label: &'static [u8], point: &C) {
+        self.append_message(label, &point.to_raw_bytes())
+    }
+
+    pub(crate) fn append_scalar<F: FftField>(&mut self, label: &'static [u8], scalar: &F) {
+        self.append_message(label, &scalar.to_raw_bytes())
+    }
+
+    /// squeeze challenge and ratchet state with it
+    pub(crate) fn challenge_scalar<F: FftField>(&mut self, label: &'static [u8]) -> F {
+        self.append_message(label, &[]);
+        let hash = self.state.clone().finalize();
+        self.append_message(b"challenge", hash.as_bytes());
+        let mut bytes = [0; 64];
+        bytes.copy_from_slice(hash.as_bytes());
+        F::from_hash(&bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Transcript;
+
+    use bls_12_381::{Fr as Scalar, G1Affine as G1};
+    use rand::rngs::OsRng;
+    use zkstd::behave::{CurveGroup, Group};
+
+    #[test]
+    fn transcript_test() {
+        let point = G1::ADDITIVE_GENERATOR;
+        let scalar = Scalar::random(OsRng);
+        let mut prover = Transcript::new(b"test");
+        let mut verifier = Transcript::new(b"test");
+        prover.append_point(b"point", &point);
+        prover.append_scalar(b"scalar", &scalar);
+        verifier.append_point(b"point", &point);
+        verifier.append_scalar(b"scalar", &scalar);
+
+        // same messages derive same challenges
+        let a: Scalar = prover.challenge_scalar(b"a");
+        assert_eq!(a, verifier.challenge_scalar(b"a"));
+        let b: Scalar = prover.challenge_scalar(b"b");
+        assert_eq!(b, verifier.challenge_scalar(b"b"));
+        assert_ne!(a, b);
+
+        // different messages derive different challenges
+        prover.append_scalar(b"scalar", &scalar);
+        verifier.append_scalar(b"scalar", &(scalar + Scalar::one()));
+        let c: Scalar = prover.challenge_scalar(b"c");
+        assert_ne!(c, verifier.challenge_scalar(b"c"));
+    }
+}
@@ -1,6 +1,85 @@
-use rand::rngs::OsRng;
-use zkstd::common::FftField;
+use rayon::prelude::*;
+use zkstd::common::{FftField, PrimeField};
 
-pub(crate) fn challenge_scalar<F: FftField>() -> F {
-    F::random(OsRng)
+const CHUNK_SIZE: usize = 1 << 10;
+
+/// 1, x, x^2, ..., x^n-1
+pub(crate) fn powers_of<F: FftField>(x: F, n: usize) -> Vec<F> {
+    let mut powers = vec![F::zero(); n];
+    powers
+        .par_chunks_mut(CHUNK_SIZE)
+        .enumerate()
+        .for_each(|(i, chunk)| {
+            let mut acc = x.pow((i * CHUNK_SIZE) as u64);
+            chunk.iter_mut().for_each(|power| {
+                *power = acc;
+                acc *= x;
+            })
+        });
+    powers
+}
+
+/// invert every non zero element with single inversion
+pub(crate) fn batch_inversion<F: PrimeField>(elements: &mut [F]) {
+    // a_0, a_0 * a_1, ..., a_0 * ... * a_n-1
+    let mut acc = F::one();
+    let prefix = elements
+        .iter()
+        .map(|element| {
+            let tmp = acc;
+            if !element.is_zero() {
+                acc *= *element;
+            }
+            tmp
+        })
+        .collect::<Vec<_>>();
+    let mut inv = acc.invert().unwrap();
+    elements
+        .iter_mut()
+        .rev()
+        .zip(prefix.into_iter().rev())
+        .filter(|(element, _)| !element.is_zero())
+        .for_each(|(element, prefix)| {
+            let tmp = inv * *element;
+            *element = inv * prefix;
+            inv = tmp;
+        });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{batch_inversion, powers_of};
+
+    use bls_12_381::Fr as Scalar;
+    use rand::rngs::OsRng;
+    use zkstd::common::{FftField, Group};
+
+    #[test]
+    fn powers_of_test() {
+        let x = Scalar::random(OsRng);
+        let powers = powers_of(x, 3000);
+        assert!(powers
+            .iter()
+            .enumerate()
+            .all(|(i, power)| *power == x.pow(i as u64)));
+    }
+
+    #[test]
+    fn batch_inversion_test() {
+        let mut elements = (0..100)
+            .map(|i| {
+                if i % 7 == 0 {
+                    Scalar::zero()
+                } else {
+                    Scalar::random(OsRng)
+                }
+            })
+            .collect::<Vec<_>>();
+        let expected = elements
+            .iter()
+            .map(|element| element.invert().unwrap_or(Scalar::zero()))
+            .collect::<Vec<_>>();
+        batch_inversion(&mut elements);
+        assert_eq!(elements, expected);
+    }
 }